@@ -0,0 +1,93 @@
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use http::{request::Builder as RequestBuilder, Response};
+use porkbun_rs::{
+    api::{ApiError, Client, Query},
+    auth::Auth,
+    endpoints::RetrieveDns,
+    RetrieveResponse,
+};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use url::Url;
+
+const RECORDS: usize = 5_000;
+
+/// A client which answers every request with the same canned body.
+struct FixtureClient {
+    body: Bytes,
+}
+
+impl FixtureClient {
+    fn retrieve(records: usize) -> Self {
+        let records = (0..records)
+            .map(|i| {
+                json!({
+                    "id": (100_000 + i).to_string(),
+                    "name": format!("host{}.example.com", i),
+                    "type": "A",
+                    "content": format!("10.0.{}.{}", i / 256 % 256, i % 256),
+                    "ttl": "600",
+                    "prio": "0",
+                    "notes": "",
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = json!({
+            "status": "SUCCESS",
+            "records": records,
+        });
+
+        Self {
+            body: serde_json::to_vec(&body).unwrap().into(),
+        }
+    }
+}
+
+impl Client for FixtureClient {
+    type Error = Infallible;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        Ok(Url::parse("https://porkbun.invalid/api/json/v3/")?.join(endpoint)?)
+    }
+
+    fn rest(
+        &self,
+        _: RequestBuilder,
+        _: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        Ok(Response::new(self.body.clone()))
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        None
+    }
+}
+
+fn retrieve(c: &mut Criterion) {
+    let client = FixtureClient::retrieve(RECORDS);
+    let endpoint = RetrieveDns::builder()
+        .domain("example.com")
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("retrieve");
+    group.bench_function("query", |b| {
+        b.iter(|| {
+            let rsp: RetrieveResponse = endpoint.query(&client).unwrap();
+            black_box(rsp)
+        })
+    });
+    // The previous decoding strategy, kept as a point of comparison.
+    group.bench_function("value_detour", |b| {
+        b.iter(|| {
+            let v: Value = serde_json::from_slice(&client.body).unwrap();
+            let rsp: RetrieveResponse = serde_json::from_value(v).unwrap();
+            black_box(rsp)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, retrieve);
+criterion_main!(benches);
@@ -0,0 +1,683 @@
+//! Solve ACME `dns-01` challenges with `_acme-challenge` TXT records.
+//!
+//! A [`Solver`] creates the record for a challenge with [`Solver::present`] and removes it
+//! with [`Solver::cleanup`]. Several challenges may be pending for the same name at once, as
+//! when a certificate covers both `example.com` and `*.example.com`: each gets its own
+//! record.
+
+use log::debug;
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{
+    api::{ApiError, Client, Query},
+    endpoints::{CreateDns, DeleteDns, DnsContent, RetrieveDns},
+    CreateResponse, DeleteResponse, Record, RetrieveResponse,
+};
+
+/// The label challenge records are published under.
+pub const CHALLENGE_LABEL: &str = "_acme-challenge";
+
+/// Errors which may occur while solving a challenge.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum AcmeError<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// A request to Porkbun failed.
+    #[error("{}", source)]
+    Api {
+        /// The source of the error.
+        #[from]
+        source: ApiError<E>,
+    },
+    /// The name being validated is not in the domain.
+    #[error("`{}` is not in the domain `{}`", fqdn, domain)]
+    NotInDomain {
+        /// The name being validated.
+        fqdn: String,
+        /// The domain.
+        domain: String,
+    },
+    /// None of the domains the name is in is managed by Porkbun with these credentials.
+    #[error("no porkbun domain found for `{}`", fqdn)]
+    NoDomain {
+        /// The name being validated.
+        fqdn: String,
+    },
+    /// The record was not listed before the timeout.
+    #[error("`{}` was not listed after {:?}", name, timeout)]
+    Timeout {
+        /// The name of the record.
+        name: String,
+        /// How long the record was waited for.
+        timeout: Duration,
+    },
+}
+
+type AcmeResult<T, E> = Result<T, AcmeError<E>>;
+
+/// The name of the challenge record for `fqdn`, relative to `domain`.
+///
+/// `fqdn` may be the name being validated, with or without a wildcard label, or the name of
+/// the challenge record itself. A trailing dot is ignored.
+pub fn challenge_name(domain: &str, fqdn: &str) -> Option<String> {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    let fqdn = fqdn.trim_end_matches('.').to_lowercase();
+    let fqdn = fqdn.strip_prefix("*.").unwrap_or(&fqdn);
+    let fqdn = fqdn
+        .strip_prefix(CHALLENGE_LABEL)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .unwrap_or(fqdn);
+
+    if fqdn == domain {
+        Some(CHALLENGE_LABEL.into())
+    } else {
+        fqdn.strip_suffix(&domain)
+            .and_then(|sub| sub.strip_suffix('.'))
+            .filter(|sub| !sub.is_empty())
+            .map(|sub| format!("{}.{}", CHALLENGE_LABEL, sub))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    domain: String,
+    name: String,
+    digest: String,
+}
+
+impl Key {
+    fn fqdn(&self) -> String {
+        format!("{}.{}", self.name, self.domain)
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        record.record_type == "TXT"
+            && record.name.eq_ignore_ascii_case(&self.fqdn())
+            && record.content.trim_matches('"') == self.digest
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// The id of the record, or `None` while it is being created.
+    id: Option<String>,
+    count: usize,
+}
+
+/// Forgets a challenge whose record could not be created, so that waiting callers retry.
+struct Creating<'a, C> {
+    solver: &'a Solver<C>,
+    key: &'a Key,
+}
+
+impl<C> Drop for Creating<'_, C> {
+    fn drop(&mut self) {
+        let mut pending = self.solver.pending();
+        if let Some(Pending { id: None, .. }) = pending.get(self.key) {
+            pending.remove(self.key);
+        }
+        self.solver.settled.notify_all();
+    }
+}
+
+/// How long to wait for a new record to be listed.
+#[derive(Debug, Clone, Copy)]
+struct Wait {
+    timeout: Duration,
+    interval: Duration,
+}
+
+/// Creates and removes challenge records.
+///
+/// The records it created are remembered so they can be removed by id. Presenting the same
+/// challenge twice only creates one record, which is removed once both are cleaned up.
+#[derive(Debug)]
+pub struct Solver<C> {
+    client: C,
+    ttl: Option<u32>,
+    wait: Option<Wait>,
+    pending: Mutex<HashMap<Key, Pending>>,
+    /// Signalled whenever a record being created gets its id or is given up on.
+    settled: Condvar,
+}
+
+impl<C> Solver<C> {
+    fn pending(&self) -> MutexGuard<'_, HashMap<Key, Pending>> {
+        // The map is consistent between statements, so a panic elsewhere does not taint it.
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The pending challenges, once `key` is no longer being created by another thread.
+    fn settled(&self, key: &Key) -> MutexGuard<'_, HashMap<Key, Pending>> {
+        let mut pending = self.pending();
+        while let Some(Pending { id: None, .. }) = pending.get(key) {
+            pending = self
+                .settled
+                .wait(pending)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        pending
+    }
+}
+
+impl<C> Solver<C>
+where
+    C: Client,
+{
+    /// Solve challenges using `client`.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            ttl: None,
+            wait: None,
+            pending: Mutex::new(HashMap::new()),
+            settled: Condvar::new(),
+        }
+    }
+
+    /// Set the TTL of the challenge records.
+    pub fn with_ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// After creating a record, list the records of the domain every `interval` until it
+    /// shows up, for up to `timeout`.
+    ///
+    /// This only checks that Porkbun knows the record, not that its nameservers serve it.
+    pub fn with_wait(mut self, timeout: Duration, interval: Duration) -> Self {
+        self.wait = Some(Wait { timeout, interval });
+        self
+    }
+
+    /// The client used to talk to Porkbun.
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    fn key(domain: &str, fqdn: &str, digest: &str) -> AcmeResult<Key, C::Error> {
+        let name = challenge_name(domain, fqdn).ok_or_else(|| AcmeError::NotInDomain {
+            fqdn: fqdn.into(),
+            domain: domain.into(),
+        })?;
+        Ok(Key {
+            domain: domain.trim_end_matches('.').to_lowercase(),
+            name,
+            digest: digest.into(),
+        })
+    }
+
+    fn records(&self, domain: &str) -> AcmeResult<Vec<Record>, C::Error> {
+        let rsp: RetrieveResponse = RetrieveDns::builder()
+            .domain(domain)
+            .build()
+            .map_err(ApiError::endpoint)?
+            .query(&self.client)?;
+        Ok(rsp.records)
+    }
+
+    /// Find the Porkbun domain `fqdn` is in, by listing the records of each of its parent
+    /// domains in turn, longest first.
+    pub fn find_domain(&self, fqdn: &str) -> AcmeResult<String, C::Error> {
+        let name = fqdn.trim_end_matches('.').to_lowercase();
+        let name = name.strip_prefix("*.").unwrap_or(&name);
+        let name = name
+            .strip_prefix(CHALLENGE_LABEL)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .unwrap_or(name);
+
+        let labels = name.split('.').collect::<Vec<_>>();
+        // Single labels are top-level domains, which are never registered with Porkbun.
+        for start in 0..labels.len().saturating_sub(1) {
+            let candidate = labels[start..].join(".");
+            match self.records(&candidate) {
+                Ok(_) => return Ok(candidate),
+                // Porkbun rejects domains it does not manage for these credentials, and names
+                // which are not valid domains, such as labels with underscores, cannot be one.
+                Err(AcmeError::Api {
+                    source: ApiError::PorkBun { .. } | ApiError::Endpoint { .. },
+                }) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(AcmeError::NoDomain { fqdn: fqdn.into() })
+    }
+
+    /// Like [`Solver::present`], finding the domain with [`Solver::find_domain`].
+    pub fn present_fqdn(&self, fqdn: &str, digest: &str) -> AcmeResult<String, C::Error> {
+        let domain = self.find_domain(fqdn)?;
+        self.present(&domain, fqdn, digest)
+    }
+
+    /// Like [`Solver::cleanup`], finding the domain with [`Solver::find_domain`].
+    pub fn cleanup_fqdn(&self, fqdn: &str, digest: &str) -> AcmeResult<(), C::Error> {
+        let domain = self.find_domain(fqdn)?;
+        self.cleanup(&domain, fqdn, digest)
+    }
+
+    /// Publish `digest`, the key authorization digest of a challenge for `fqdn`, in the
+    /// Porkbun domain `domain`, returning the id of the record.
+    pub fn present(&self, domain: &str, fqdn: &str, digest: &str) -> AcmeResult<String, C::Error> {
+        let key = Self::key(domain, fqdn, digest)?;
+
+        {
+            // Claim the challenge so that presenting it again waits for this record rather
+            // than creating another one; other challenges go ahead meanwhile.
+            let mut pending = self.settled(&key);
+            if let Some(existing) = pending.get_mut(&key) {
+                existing.count += 1;
+                return Ok(existing.id.clone().unwrap_or_default());
+            }
+            pending.insert(key.clone(), Pending { id: None, count: 1 });
+        }
+
+        let creating = Creating {
+            solver: self,
+            key: &key,
+        };
+        let mut endpoint = CreateDns::builder();
+        endpoint
+            .domain(key.domain.as_str())
+            .name(key.name.as_str())
+            .record(DnsContent::Txt {
+                content: key.digest.clone(),
+            });
+        if let Some(ttl) = self.ttl {
+            endpoint.ttl(ttl);
+        }
+        let rsp: CreateResponse = endpoint
+            .build()
+            .map_err(ApiError::endpoint)?
+            .query(&self.client)?;
+        debug!(target: "porkbun", "created challenge record {} for {}", rsp.id, key.fqdn());
+        if let Some(existing) = self.pending().get_mut(&key) {
+            existing.id = Some(rsp.id.clone());
+        }
+        drop(creating);
+        let id = rsp.id;
+
+        if let Some(wait) = self.wait {
+            self.wait_for(&key, &id, wait)?;
+        }
+
+        Ok(id)
+    }
+
+    fn wait_for(&self, key: &Key, id: &str, wait: Wait) -> AcmeResult<(), C::Error> {
+        let start = Instant::now();
+        loop {
+            let listed = self
+                .records(&key.domain)?
+                .iter()
+                .any(|record| record.id == id && key.matches(record));
+            if listed {
+                return Ok(());
+            }
+            if start.elapsed() + wait.interval > wait.timeout {
+                return Err(AcmeError::Timeout {
+                    name: key.fqdn(),
+                    timeout: wait.timeout,
+                });
+            }
+            thread::sleep(wait.interval);
+        }
+    }
+
+    /// Remove the record published by [`Solver::present`] for the same arguments.
+    ///
+    /// Records this solver did not create, such as those created by another process, are
+    /// found by listing the domain. Nothing is done if there is no such record.
+    pub fn cleanup(&self, domain: &str, fqdn: &str, digest: &str) -> AcmeResult<(), C::Error> {
+        let key = Self::key(domain, fqdn, digest)?;
+
+        let ids = {
+            let mut pending = self.settled(&key);
+            match pending.get_mut(&key) {
+                Some(existing) if existing.count > 1 => {
+                    existing.count -= 1;
+                    return Ok(());
+                }
+                Some(_) => pending
+                    .remove(&key)
+                    .and_then(|existing| existing.id)
+                    .into_iter()
+                    .collect(),
+                None => Vec::new(),
+            }
+        };
+
+        let ids = if ids.is_empty() {
+            self.records(&key.domain)?
+                .into_iter()
+                .filter(|record| key.matches(record))
+                .map(|record| record.id)
+                .collect()
+        } else {
+            ids
+        };
+
+        for id in ids {
+            let _: DeleteResponse = DeleteDns::builder()
+                .domain(key.domain.as_str())
+                .id(id.as_str())
+                .build()
+                .map_err(ApiError::endpoint)?
+                .query(&self.client)?;
+            debug!(target: "porkbun", "deleted challenge record {} for {}", id, key.fqdn());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use std::{
+        sync::{Condvar, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use bytes::Bytes;
+    use http::{request::Builder as RequestBuilder, Response};
+    use url::Url;
+
+    use crate::{
+        api::{ApiError, Client},
+        auth::Auth,
+        test::{client::TestClientError, FakePorkbun},
+    };
+
+    use super::{challenge_name, AcmeError, Solver};
+
+    /// Holds record creations back until two were under way at once, for a second at most.
+    struct Overlapping {
+        fake: FakePorkbun,
+        creating: Mutex<(usize, usize)>,
+        changed: Condvar,
+    }
+
+    impl Overlapping {
+        fn new(fake: FakePorkbun) -> Self {
+            Self {
+                fake,
+                creating: Mutex::new((0, 0)),
+                changed: Condvar::new(),
+            }
+        }
+
+        /// The most record creations which were under way at once.
+        fn most(&self) -> usize {
+            self.creating.lock().unwrap().1
+        }
+    }
+
+    impl Client for Overlapping {
+        type Error = TestClientError;
+
+        fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+            Client::rest_endpoint(&self.fake, endpoint)
+        }
+
+        fn rest(
+            &self,
+            request: RequestBuilder,
+            body: Vec<u8>,
+        ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+            let create = request
+                .uri_ref()
+                .is_some_and(|uri| uri.path().contains("/dns/create/"));
+            if !create {
+                return Client::rest(&self.fake, request, body);
+            }
+
+            {
+                let mut creating = self.creating.lock().unwrap();
+                creating.0 += 1;
+                creating.1 = creating.1.max(creating.0);
+                self.changed.notify_all();
+                let _ = self
+                    .changed
+                    .wait_timeout_while(creating, Duration::from_secs(1), |creating| creating.1 < 2)
+                    .unwrap();
+            }
+            let rsp = Client::rest(&self.fake, request, body);
+            self.creating.lock().unwrap().0 -= 1;
+            rsp
+        }
+
+        fn auth(&self) -> Option<Auth> {
+            Client::auth(&self.fake)
+        }
+    }
+
+    fn fake() -> FakePorkbun {
+        FakePorkbun::new().with_domain("example.com")
+    }
+
+    fn txt(fake: &FakePorkbun) -> Vec<(String, String)> {
+        let mut records = fake
+            .records("example.com")
+            .unwrap()
+            .into_iter()
+            .filter(|record| record.record_type == "TXT")
+            .map(|record| (record.name, record.content))
+            .collect::<Vec<_>>();
+        records.sort();
+        records
+    }
+
+    #[test]
+    fn names() {
+        let name = |fqdn| challenge_name("example.com", fqdn);
+        assert_eq!(name("example.com").unwrap(), "_acme-challenge");
+        assert_eq!(name("*.example.com.").unwrap(), "_acme-challenge");
+        assert_eq!(name("WWW.example.com").unwrap(), "_acme-challenge.www");
+        assert_eq!(
+            name("_acme-challenge.a.b.example.com.").unwrap(),
+            "_acme-challenge.a.b",
+        );
+        assert_eq!(name("example.org"), None);
+        assert_eq!(name("badexample.com"), None);
+    }
+
+    #[test]
+    fn present_and_cleanup() {
+        let fake = fake();
+        let solver = Solver::new(fake.clone());
+
+        solver
+            .present("example.com", "www.example.com", "d1")
+            .unwrap();
+        assert_eq!(
+            txt(&fake),
+            [("_acme-challenge.www.example.com".into(), "d1".into())],
+        );
+
+        solver
+            .cleanup("example.com", "www.example.com", "d1")
+            .unwrap();
+        assert_eq!(txt(&fake), []);
+    }
+
+    #[test]
+    fn concurrent_challenges() {
+        let fake = fake();
+        let solver = Solver::new(fake.clone());
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                solver
+                    .present("example.com", "example.com", "apex")
+                    .unwrap()
+            });
+            scope.spawn(|| {
+                solver
+                    .present("example.com", "*.example.com", "wild")
+                    .unwrap()
+            });
+        });
+        assert_eq!(
+            txt(&fake),
+            [
+                ("_acme-challenge.example.com".into(), "apex".into()),
+                ("_acme-challenge.example.com".into(), "wild".into()),
+            ],
+        );
+
+        solver
+            .cleanup("example.com", "*.example.com", "wild")
+            .unwrap();
+        assert_eq!(
+            txt(&fake),
+            [("_acme-challenge.example.com".into(), "apex".into())],
+        );
+    }
+
+    #[test]
+    fn parallel_challenges() {
+        let fake = fake();
+        let solver = Solver::new(Overlapping::new(fake.clone()));
+
+        thread::scope(|scope| {
+            for (fqdn, digest) in [("example.com", "apex"), ("*.www.example.com", "wild")] {
+                let solver = &solver;
+                scope.spawn(move || solver.present("example.com", fqdn, digest).unwrap());
+            }
+        });
+        assert_eq!(solver.client().most(), 2);
+        assert_eq!(txt(&fake).len(), 2);
+
+        solver
+            .cleanup("example.com", "example.com", "apex")
+            .unwrap();
+        solver
+            .cleanup("example.com", "*.www.example.com", "wild")
+            .unwrap();
+        assert_eq!(txt(&fake), []);
+    }
+
+    #[test]
+    fn same_challenge_in_parallel() {
+        let fake = fake();
+        let solver = Solver::new(fake.clone());
+
+        let ids = thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|_| scope.spawn(|| solver.present("example.com", "example.com", "d1")))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert!(ids.iter().all(|id| *id == ids[0]));
+        assert_eq!(txt(&fake).len(), 1);
+    }
+
+    #[test]
+    fn same_challenge_twice() {
+        let fake = fake();
+        let solver = Solver::new(fake.clone());
+
+        let first = solver.present("example.com", "example.com", "d1").unwrap();
+        let second = solver.present("example.com", "example.com", "d1").unwrap();
+        assert_eq!(first, second);
+
+        solver.cleanup("example.com", "example.com", "d1").unwrap();
+        assert_eq!(txt(&fake).len(), 1);
+        solver.cleanup("example.com", "example.com", "d1").unwrap();
+        assert_eq!(txt(&fake), []);
+    }
+
+    #[test]
+    fn cleanup_untracked() {
+        let fake = fake();
+        Solver::new(fake.clone())
+            .present("example.com", "www.example.com", "d1")
+            .unwrap();
+
+        let solver = Solver::new(fake.clone());
+        solver
+            .cleanup("example.com", "www.example.com", "d2")
+            .unwrap();
+        assert_eq!(txt(&fake).len(), 1);
+        solver
+            .cleanup("example.com", "www.example.com", "d1")
+            .unwrap();
+        assert_eq!(txt(&fake), []);
+    }
+
+    #[test]
+    fn wait() {
+        let solver =
+            Solver::new(fake()).with_wait(Duration::from_secs(1), Duration::from_millis(10));
+        solver.present("example.com", "example.com", "d1").unwrap();
+    }
+
+    #[test]
+    fn find_domain() {
+        let fake = fake().with_domain("example.co.uk");
+        let solver = Solver::new(fake.clone());
+
+        assert_eq!(
+            solver.find_domain("a.b.example.com").unwrap(),
+            "example.com"
+        );
+        assert_eq!(
+            solver
+                .find_domain("_acme-challenge.example.co.uk.")
+                .unwrap(),
+            "example.co.uk",
+        );
+        assert!(matches!(
+            solver.find_domain("example.org").unwrap_err(),
+            AcmeError::NoDomain { .. },
+        ));
+
+        solver.present_fqdn("*.www.example.co.uk", "d1").unwrap();
+        let records = fake.records("example.co.uk").unwrap();
+        assert_eq!(records[0].name, "_acme-challenge.www.example.co.uk");
+        solver.cleanup_fqdn("www.example.co.uk", "d1").unwrap();
+        assert!(fake.records("example.co.uk").unwrap().is_empty());
+    }
+
+    #[test]
+    fn find_domain_skips_invalid_domains() {
+        let fake = fake();
+        let solver = Solver::new(fake.clone());
+
+        assert_eq!(
+            solver
+                .find_domain("_acme-challenge.x_y.example.com")
+                .unwrap(),
+            "example.com",
+        );
+
+        solver.present_fqdn("a.x_y.example.com", "d1").unwrap();
+        let records = fake.records("example.com").unwrap();
+        assert_eq!(records[0].name, "_acme-challenge.a.x_y.example.com");
+    }
+
+    #[test]
+    fn not_in_domain() {
+        let err = Solver::new(fake())
+            .present("example.com", "example.org", "d1")
+            .unwrap_err();
+        if let AcmeError::NotInDomain { fqdn, domain } = err {
+            assert_eq!(fqdn, "example.org");
+            assert_eq!(domain, "example.com");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use futures_util::{stream, StreamExt};
+use std::{
+    error::Error,
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::api::{ApiError, AsyncClient, AsyncQuery};
+
+/// How many queries run at once by default.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Queries run together against an asynchronous client.
+///
+/// Endpoints of different types can be mixed by boxing them, as in
+/// `api::ignore(Box::new(endpoint) as Box<dyn Endpoint + Send + Sync>)`.
+#[derive(Debug, Clone)]
+pub struct Batch<Q, T> {
+    queries: Vec<Q>,
+    concurrency: usize,
+    stop_on_failure: bool,
+    output: PhantomData<fn() -> T>,
+}
+
+/// What became of a query of a [`Batch`].
+#[derive(Debug)]
+pub enum BatchItem<T, E>
+where
+    E: Error + Send + Sync + 'static,
+{
+    /// The query succeeded.
+    Succeeded(T),
+    /// The query failed.
+    Failed(ApiError<E>),
+    /// The query was not sent because an earlier one failed.
+    Skipped,
+}
+
+/// The results of a [`Batch`], in the order of its queries.
+#[derive(Debug)]
+pub struct BatchReport<T, E>
+where
+    E: Error + Send + Sync + 'static,
+{
+    /// What became of each query.
+    pub items: Vec<BatchItem<T, E>>,
+}
+
+impl<Q, T> Batch<Q, T> {
+    /// Run `queries`, 4 at a time.
+    pub fn new<I>(queries: I) -> Self
+    where
+        I: IntoIterator<Item = Q>,
+    {
+        Self {
+            queries: queries.into_iter().collect(),
+            concurrency: DEFAULT_CONCURRENCY,
+            stop_on_failure: false,
+            output: PhantomData,
+        }
+    }
+
+    /// Run `concurrency` queries at a time, at least one.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Do not send any more queries once one failed.
+    ///
+    /// Queries already sent still complete; the others are reported as skipped.
+    pub fn stop_on_failure(mut self) -> Self {
+        self.stop_on_failure = true;
+        self
+    }
+
+    /// Run the queries against `client`.
+    pub async fn run<C>(&self, client: &C) -> BatchReport<T, C::Error>
+    where
+        Q: AsyncQuery<T, C>,
+        C: AsyncClient,
+    {
+        let failed = AtomicBool::new(false);
+        let failed = &failed;
+        let items = stream::iter(&self.queries)
+            .map(|query| async move {
+                if failed.load(Ordering::SeqCst) {
+                    return BatchItem::Skipped;
+                }
+                match query.query_async(client).await {
+                    Ok(output) => BatchItem::Succeeded(output),
+                    Err(err) => {
+                        if self.stop_on_failure {
+                            failed.store(true, Ordering::SeqCst);
+                        }
+                        BatchItem::Failed(err)
+                    }
+                }
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+        BatchReport { items }
+    }
+}
+
+impl<T, E> BatchReport<T, E>
+where
+    E: Error + Send + Sync + 'static,
+{
+    /// Whether every query succeeded.
+    pub fn is_success(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| matches!(item, BatchItem::Succeeded(_)))
+    }
+
+    /// The outputs of the queries which succeeded, with their index.
+    pub fn successes(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| match item {
+                BatchItem::Succeeded(output) => Some((index, output)),
+                _ => None,
+            })
+    }
+
+    /// The errors of the queries which failed, with their index.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &ApiError<E>)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| match item {
+                BatchItem::Failed(err) => Some((index, err)),
+                _ => None,
+            })
+    }
+
+    /// The indices of the queries which were not sent.
+    pub fn skipped(&self) -> impl Iterator<Item = usize> + '_ {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| matches!(item, BatchItem::Skipped))
+            .map(|(index, _)| index)
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use crate::{
+        api::{self, ApiError, Endpoint},
+        endpoints::{CreateDns, DeleteDns, DnsContent},
+        test::FakePorkbun,
+        CreateResponse,
+    };
+
+    use super::{Batch, BatchItem};
+
+    fn create(content: &str) -> CreateDns<'static> {
+        CreateDns::builder()
+            .domain("example.com")
+            .record(DnsContent::A {
+                content: content.parse().unwrap(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn ordered_report() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let queries = vec![
+            create("192.0.2.1"),
+            create("192.0.2.2"),
+            create("192.0.2.1"),
+            create("192.0.2.3"),
+        ];
+
+        let report = Batch::<_, CreateResponse>::new(queries)
+            .with_concurrency(2)
+            .run(&fake)
+            .await;
+        assert!(!report.is_success());
+        let successes = report.successes().map(|(index, _)| index);
+        assert_eq!(successes.collect::<Vec<_>>(), [0, 1, 3]);
+        let failures = report.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 1);
+        if let ApiError::PorkBun { message, .. } = failures[0].1 {
+            assert_eq!(message, "Create error: Duplicate record.");
+        } else {
+            panic!("unexpected error: {}", failures[0].1);
+        }
+        assert_eq!(failures[0].0, 2);
+        assert_eq!(fake.records("example.com").unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn stop_on_failure() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let delete = DeleteDns::builder()
+            .domain("example.com")
+            .id("1")
+            .build()
+            .unwrap();
+        let queries: Vec<Box<dyn Endpoint + Send + Sync>> = vec![
+            Box::new(create("192.0.2.1")),
+            Box::new(delete),
+            Box::new(create("192.0.2.2")),
+            Box::new(create("192.0.2.3")),
+        ];
+
+        let report = Batch::new(queries.into_iter().map(api::ignore))
+            .with_concurrency(1)
+            .stop_on_failure()
+            .run(&fake)
+            .await;
+        assert!(matches!(report.items[0], BatchItem::Succeeded(())));
+        assert!(matches!(report.items[1], BatchItem::Failed(_)));
+        assert_eq!(report.skipped().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(fake.records("example.com").unwrap().len(), 1);
+    }
+}
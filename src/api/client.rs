@@ -1,12 +1,14 @@
 use super::error::ApiError;
+use crate::auth::Auth;
+#[cfg(feature = "async")]
 use async_trait::async_trait;
 use bytes::Bytes;
 use http::{request::Builder as RequestBuilder, Response};
-use serde_json::{Map, Value};
 use std::error::Error;
 use url::Url;
 
 /// A trait representing an asynchronous client which can communicate with a Porkbun instance.
+#[cfg(feature = "async")]
 #[async_trait]
 pub trait AsyncClient {
     type Error: Error + Send + Sync + 'static;
@@ -23,10 +25,15 @@ pub trait AsyncClient {
         body: Vec<u8>,
     ) -> Result<Response<Bytes>, ApiError<Self::Error>>;
 
-    fn auth(&self) -> Map<String, Value>;
+    /// The credentials added to the body of every request, if any.
+    ///
+    /// The body handed to the client is the only copy of them made while building a request;
+    /// the client should wipe it once it has been sent if its transport allows.
+    fn auth(&self) -> Option<Auth>;
 }
 
 /// A trait representing a client which can communicate with a Porkbun instance.
+#[cfg(feature = "sync")]
 pub trait Client {
     /// The errors which may occur for this client.
     type Error: Error + Send + Sync + 'static;
@@ -43,5 +50,9 @@ pub trait Client {
         body: Vec<u8>,
     ) -> Result<Response<Bytes>, ApiError<Self::Error>>;
 
-    fn auth(&self) -> Map<String, Value>;
+    /// The credentials added to the body of every request, if any.
+    ///
+    /// The body handed to the client is the only copy of them made while building a request;
+    /// the client should wipe it once it has been sent if its transport allows.
+    fn auth(&self) -> Option<Auth>;
 }
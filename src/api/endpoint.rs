@@ -1,14 +1,25 @@
-use super::{
-    client::{AsyncClient, Client},
-    error::ApiError,
-    query::{self, AsyncQuery},
-    Query,
-};
+#[cfg(feature = "async")]
+use super::{client::AsyncClient, query::AsyncQuery};
+#[cfg(feature = "sync")]
+use super::{client::Client, query::Query};
+#[cfg(any(feature = "sync", feature = "async"))]
+use super::{error::ApiError, instrument::QuerySpan, query};
+#[cfg(feature = "async")]
 use async_trait::async_trait;
-use http::{header, Method, Request};
-use serde::de::DeserializeOwned;
+#[cfg(any(feature = "sync", feature = "async"))]
+use bytes::Bytes;
+use http::Method;
+#[cfg(any(feature = "sync", feature = "async"))]
+use http::Response;
+#[cfg(any(feature = "sync", feature = "async"))]
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize,
+};
 use serde_json::{Map, Value};
 use std::borrow::Cow;
+#[cfg(any(feature = "sync", feature = "async"))]
+use std::error::Error;
 
 /// A trait for providing the necessary information for a single REST API endpoint.
 pub trait Endpoint {
@@ -24,8 +35,117 @@ pub trait Endpoint {
     fn body(&self) -> Map<String, Value> {
         Map::default()
     }
+
+    /// The domain the endpoint acts on, if any.
+    fn domain(&self) -> Option<Cow<'_, str>> {
+        None
+    }
 }
 
+impl<E> Endpoint for Box<E>
+where
+    E: Endpoint + ?Sized,
+{
+    fn method(&self) -> Method {
+        (**self).method()
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        (**self).endpoint()
+    }
+
+    fn body(&self) -> Map<String, Value> {
+        (**self).body()
+    }
+
+    fn domain(&self) -> Option<Cow<'_, str>> {
+        (**self).domain()
+    }
+}
+
+/// The common shape of every Porkbun response.
+///
+/// Porkbun reports a `status` (and sometimes a `message`) next to the endpoint-specific
+/// fields, which are flattened into `payload`.
+#[cfg(any(feature = "sync", feature = "async"))]
+#[derive(Deserialize)]
+struct Envelope<T> {
+    status: Option<String>,
+    message: Option<String>,
+    #[serde(flatten)]
+    payload: T,
+}
+
+#[cfg(any(feature = "sync", feature = "async"))]
+impl<T> Envelope<T> {
+    /// The payload, unless Porkbun reported an error despite a success status.
+    fn into_payload<E>(self) -> Result<T, ApiError<E>>
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        match self.status {
+            Some(status) if status == "ERROR" => Err(ApiError::PorkBun {
+                status,
+                message: self.message.unwrap_or_default(),
+            }),
+            _ => Ok(self.payload),
+        }
+    }
+}
+
+/// Decode a response from Porkbun into the payload type of an endpoint.
+///
+/// Successful responses are deserialized straight from the body; a `Value` is only built when
+/// something went wrong so that errors can carry the offending object.
+#[cfg(any(feature = "sync", feature = "async"))]
+fn decode<T, E>(rsp: &Response<Bytes>) -> Result<T, ApiError<E>>
+where
+    T: DeserializeOwned,
+    E: Error + Send + Sync + 'static,
+{
+    if !rsp.status().is_success() {
+        let v = serde_json::from_slice(rsp.body())?;
+        return Err(ApiError::from_porkbun(v));
+    }
+
+    match serde_json::from_slice::<Envelope<T>>(rsp.body()) {
+        Ok(envelope) => envelope.into_payload(),
+        Err(err) => {
+            // Only now look at the body as a `Value`: it may be an error Porkbun reported with a
+            // success status, otherwise re-running the conversion reports the failure without
+            // position information, as callers have always seen it.
+            let v = serde_json::from_slice::<Value>(rsp.body())?;
+            if v.get("status").and_then(Value::as_str) == Some("ERROR") {
+                return Err(ApiError::from_porkbun(v));
+            }
+            let source = serde_json::from_value::<Envelope<T>>(v)
+                .err()
+                .unwrap_or(err);
+            Err(ApiError::data_type::<T>(source))
+        }
+    }
+}
+
+/// Check the status of a response whose payload is not wanted.
+///
+/// Bodies which are not JSON objects are accepted as long as the HTTP status is a success.
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) fn check_status<E>(rsp: &Response<Bytes>) -> Result<(), ApiError<E>>
+where
+    E: Error + Send + Sync + 'static,
+{
+    if !rsp.status().is_success() {
+        let v = serde_json::from_slice(rsp.body())?;
+        return Err(ApiError::from_porkbun(v));
+    }
+
+    match serde_json::from_slice::<Envelope<IgnoredAny>>(rsp.body()) {
+        Ok(envelope) => envelope.into_payload().map(|_| ()),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(feature = "sync")]
 impl<E, T, C> Query<T, C> for E
 where
     E: Endpoint,
@@ -33,32 +153,22 @@ where
     C: Client,
 {
     fn query(&self, client: &C) -> Result<T, ApiError<C::Error>> {
-        let url = client.rest_endpoint(&self.endpoint())?;
-        let mut req = Request::builder()
-            .method(self.method())
-            .uri(query::url_to_http_uri(url));
-        let mut body = self.body();
-        body.append(&mut client.auth());
-
-        let data = match body.len() {
-            0 => vec![],
-            _ => {
-                req = req.header(header::CONTENT_TYPE, "application/json");
-                serde_json::to_vec(&body)?
-            }
-        };
-
-        let rsp = client.rest(req, data)?;
-        let status = rsp.status();
-        let v = serde_json::from_slice(rsp.body())?;
-        if !status.is_success() {
-            return Err(ApiError::from_porkbun(v));
-        }
+        let span = QuerySpan::new(self);
+        let result = span.in_scope(|| {
+            let url = client.rest_endpoint(&self.endpoint())?;
+            let (req, data) = query::request(self, url, client.auth())?;
+
+            let rsp = client.rest(req, data);
+            span.response(&rsp);
+            let rsp = rsp?;
+            decode(&rsp)
+        });
 
-        serde_json::from_value::<T>(v).map_err(ApiError::data_type::<T>)
+        span.finish(result)
     }
 }
 
+#[cfg(feature = "async")]
 #[async_trait]
 impl<E, T, C> AsyncQuery<T, C> for E
 where
@@ -67,33 +177,24 @@ where
     C: AsyncClient + Sync,
 {
     async fn query_async(&self, client: &C) -> Result<T, ApiError<C::Error>> {
-        let url = client.rest_endpoint(&self.endpoint())?;
-        let mut req = Request::builder()
-            .method(self.method())
-            .uri(query::url_to_http_uri(url));
-        let mut body = self.body();
-        body.append(&mut client.auth());
-
-        let data = match body.len() {
-            0 => vec![],
-            _ => {
-                req = req.header(header::CONTENT_TYPE, "application/json");
-                serde_json::to_vec(&body)?
-            }
-        };
-
-        let rsp = client.rest_async(req, data).await?;
-        let status = rsp.status();
-        let v = serde_json::from_slice(rsp.body())?;
-        if !status.is_success() {
-            return Err(ApiError::from_porkbun(v));
-        }
-
-        serde_json::from_value::<T>(v).map_err(ApiError::data_type::<T>)
+        let span = QuerySpan::new(self);
+        let result = span
+            .instrument(async {
+                let url = client.rest_endpoint(&self.endpoint())?;
+                let (req, data) = query::request(self, url, client.auth())?;
+
+                let rsp = client.rest_async(req, data).await;
+                span.response(&rsp);
+                let rsp = rsp?;
+                decode(&rsp)
+            })
+            .await;
+
+        span.finish(result)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sync", feature = "async"))]
 mod tests {
     use http::{Method, StatusCode};
     use serde::Deserialize;
@@ -242,6 +343,42 @@ mod tests {
         assert_eq!(res.value, 0);
     }
 
+    #[test]
+    fn test_good_deserialization_with_status() {
+        let endpoint = ExpectedUrl::builder().endpoint("dummy").build().unwrap();
+        let client = SingleTestClient::new_json(
+            endpoint,
+            &json!({
+                "status": "SUCCESS",
+                "value": 3,
+            }),
+        );
+
+        let res: DummyResult = Dummy.query(&client).unwrap();
+        assert_eq!(res.value, 3);
+    }
+
+    #[test]
+    fn test_porkbun_error_status_on_success() {
+        let endpoint = ExpectedUrl::builder().endpoint("dummy").build().unwrap();
+        let client = SingleTestClient::new_json(
+            endpoint,
+            &json!({
+                "status": "ERROR",
+                "message": "Invalid domain.",
+            }),
+        );
+
+        let res: Result<DummyResult, _> = Dummy.query(&client);
+        let err = res.unwrap_err();
+        if let ApiError::PorkBun { message, status } = err {
+            assert_eq!(message, "Invalid domain.");
+            assert_eq!(status, "ERROR");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
     #[tokio::test]
     async fn test_good_deserialization_async() {
         let endpoint = ExpectedUrl::builder().endpoint("dummy").build().unwrap();
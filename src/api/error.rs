@@ -1,7 +1,11 @@
+#[cfg(any(feature = "sync", feature = "async"))]
 use serde::Deserialize;
-use std::{any, error::Error};
+#[cfg(any(feature = "sync", feature = "async"))]
+use std::any;
+use std::{error::Error, fmt::Display};
 use thiserror::Error;
 
+#[cfg(any(feature = "sync", feature = "async"))]
 #[derive(Debug, Deserialize)]
 struct Status {
     status: String,
@@ -67,6 +71,12 @@ where
         /// The full object from PorkBun.
         obj: serde_json::Value,
     },
+    /// An endpoint could not be built, for instance because its domain is not valid.
+    #[error("invalid endpoint: {}", message)]
+    Endpoint {
+        /// What is wrong.
+        message: String,
+    },
     /// JSON deserialization from PorkBun failed.
     #[error("could not parse JSON response: {}", source)]
     Json {
@@ -85,6 +95,17 @@ where
         ApiError::Client { source }
     }
 
+    /// Create an API error for an endpoint builder which failed.
+    pub fn endpoint<M>(message: M) -> Self
+    where
+        M: Display,
+    {
+        ApiError::Endpoint {
+            message: message.to_string(),
+        }
+    }
+
+    #[cfg(any(feature = "sync", feature = "async"))]
     pub(crate) fn from_porkbun(value: serde_json::Value) -> Self {
         match serde_json::from_value::<Status>(value.clone()) {
             Ok(status) => ApiError::PorkBun {
@@ -95,6 +116,7 @@ where
         }
     }
 
+    #[cfg(any(feature = "sync", feature = "async"))]
     pub(crate) fn data_type<T>(source: serde_json::Error) -> Self {
         ApiError::DataType {
             source,
@@ -103,7 +125,7 @@ where
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, any(feature = "sync", feature = "async")))]
 mod tests {
     use serde_json::json;
     use thiserror::Error;
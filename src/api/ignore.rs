@@ -1,6 +1,11 @@
-use crate::api::{query, ApiError, AsyncClient, AsyncQuery, Client, Endpoint, Query};
+#[cfg(any(feature = "sync", feature = "async"))]
+use crate::api::{endpoint, instrument::QuerySpan, query, ApiError, Endpoint};
+#[cfg(feature = "async")]
+use crate::api::{AsyncClient, AsyncQuery};
+#[cfg(feature = "sync")]
+use crate::api::{Client, Query};
+#[cfg(feature = "async")]
 use async_trait::async_trait;
-use http::{header, Request};
 
 /// A query modifier that ignores the data returned from an endpoint.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,37 +18,29 @@ pub fn ignore<E>(endpoint: E) -> Ignore<E> {
     Ignore { endpoint }
 }
 
+#[cfg(feature = "sync")]
 impl<E, C> Query<(), C> for Ignore<E>
 where
     E: Endpoint,
     C: Client,
 {
     fn query(&self, client: &C) -> Result<(), ApiError<C::Error>> {
-        let url = client.rest_endpoint(&self.endpoint.endpoint())?;
-        let mut req = Request::builder()
-            .method(self.endpoint.method())
-            .uri(query::url_to_http_uri(url));
-        let mut body = self.endpoint.body();
-        body.append(&mut client.auth());
-
-        let data = match body.len() {
-            0 => vec![],
-            _ => {
-                req = req.header(header::CONTENT_TYPE, "application/json");
-                serde_json::to_vec(&body)?
-            }
-        };
-
-        let rsp = client.rest(req, data)?;
-        if !rsp.status().is_success() {
-            let v = serde_json::from_slice(rsp.body())?;
-            return Err(ApiError::from_porkbun(v));
-        }
+        let span = QuerySpan::new(&self.endpoint);
+        let result = span.in_scope(|| {
+            let url = client.rest_endpoint(&self.endpoint.endpoint())?;
+            let (req, data) = query::request(&self.endpoint, url, client.auth())?;
+
+            let rsp = client.rest(req, data);
+            span.response(&rsp);
+            let rsp = rsp?;
+            endpoint::check_status(&rsp)
+        });
 
-        Ok(())
+        span.finish(result)
     }
 }
 
+#[cfg(feature = "async")]
 #[async_trait]
 impl<E, C> AsyncQuery<(), C> for Ignore<E>
 where
@@ -51,33 +48,24 @@ where
     C: AsyncClient + Sync,
 {
     async fn query_async(&self, client: &C) -> Result<(), ApiError<C::Error>> {
-        let url = client.rest_endpoint(&self.endpoint.endpoint())?;
-        let mut req = Request::builder()
-            .method(self.endpoint.method())
-            .uri(query::url_to_http_uri(url));
-
-        let mut body = self.endpoint.body();
-        body.append(&mut client.auth());
-
-        let data = match body.len() {
-            0 => vec![],
-            _ => {
-                req = req.header(header::CONTENT_TYPE, "application/json");
-                serde_json::to_vec(&body)?
-            }
-        };
-
-        let rsp = client.rest_async(req, data).await?;
-        if !rsp.status().is_success() {
-            let v = serde_json::from_slice(rsp.body())?;
-            return Err(ApiError::from_porkbun(v));
-        }
-
-        Ok(())
+        let span = QuerySpan::new(&self.endpoint);
+        let result = span
+            .instrument(async {
+                let url = client.rest_endpoint(&self.endpoint.endpoint())?;
+                let (req, data) = query::request(&self.endpoint, url, client.auth())?;
+
+                let rsp = client.rest_async(req, data).await;
+                span.response(&rsp);
+                let rsp = rsp?;
+                endpoint::check_status(&rsp)
+            })
+            .await;
+
+        span.finish(result)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sync", feature = "async"))]
 mod tests {
     use std::borrow::Cow;
 
@@ -99,11 +87,6 @@ mod tests {
         }
     }
 
-    #[derive(Debug)]
-    struct DummyResult {
-        value: u8,
-    }
-
     #[test]
     fn test_porkbun_non_json_response() {
         let endpoint = ExpectedUrl::builder().endpoint("dummy").build().unwrap();
@@ -164,6 +147,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_porkbun_error_detection_success_status() {
+        let endpoint = ExpectedUrl::builder().endpoint("dummy").build().unwrap();
+        let client = SingleTestClient::new_json(
+            endpoint,
+            &json!({
+                "message": "dummy error message",
+                "status": "ERROR",
+            }),
+        );
+
+        let err = api::ignore(Dummy).query(&client).unwrap_err();
+        if let ApiError::PorkBun { message, status } = err {
+            assert_eq!(message, "dummy error message");
+            assert_eq!(status, "ERROR")
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_porkbun_error_detection_success_status_async() {
+        let endpoint = ExpectedUrl::builder().endpoint("dummy").build().unwrap();
+        let client = SingleTestClient::new_json(
+            endpoint,
+            &json!({
+                "message": "dummy error message",
+                "status": "ERROR",
+                "records": [],
+            }),
+        );
+
+        let err = api::ignore(Dummy).query_async(&client).await.unwrap_err();
+        assert!(matches!(err, ApiError::PorkBun { .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_porkbun_success_status() {
+        let endpoint = ExpectedUrl::builder().endpoint("dummy").build().unwrap();
+        let client = SingleTestClient::new_json(
+            endpoint,
+            &json!({
+                "status": "SUCCESS",
+                "records": [],
+            }),
+        );
+
+        api::ignore(Dummy).query(&client).unwrap()
+    }
+
     #[test]
     fn test_porkbun_error_detection_unknown() {
         let endpoint = ExpectedUrl::builder()
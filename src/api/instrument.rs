@@ -0,0 +1,338 @@
+use bytes::Bytes;
+use http::Response;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::{error::Error, time::Instant};
+
+use super::{endpoint::Endpoint, error::ApiError};
+
+/// Record the attempt number of the query being sent.
+///
+/// Clients or middleware which send a request again call this before each new attempt, from
+/// within the query, so that the `attempt` field of its `porkbun.query` span is accurate.
+/// Without the `tracing` feature this does nothing.
+pub fn record_attempt(attempt: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("attempt", attempt);
+    #[cfg(not(feature = "tracing"))]
+    let _ = attempt;
+}
+
+/// The span covering a single query.
+///
+/// With the `tracing` feature, every query runs in a `porkbun.query` span carrying:
+///
+/// - `endpoint`: the path of the endpoint, relative to the API root;
+/// - `domain`: the domain the endpoint acts on, if any;
+/// - `attempt`: the attempt number, 1 unless the client retries and records another one with
+///   [`record_attempt`];
+/// - `http.status`: the HTTP status of the response;
+/// - `latency_ms`: the time spent waiting for the response, or for the client to fail;
+/// - `porkbun.status` and `porkbun.message`: the error reported by Porkbun, if any.
+///
+/// Request bodies, and therefore credentials, are never recorded. Without the feature this
+/// does nothing.
+pub(crate) struct QuerySpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    start: Instant,
+}
+
+impl QuerySpan {
+    pub(crate) fn new<E>(endpoint: &E) -> Self
+    where
+        E: Endpoint + ?Sized,
+    {
+        #[cfg(feature = "tracing")]
+        let span = {
+            let span = tracing::debug_span!(
+                target: "porkbun",
+                "porkbun.query",
+                endpoint = %endpoint.endpoint(),
+                domain = tracing::field::Empty,
+                attempt = 1u32,
+                http.status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                porkbun.status = tracing::field::Empty,
+                porkbun.message = tracing::field::Empty,
+            );
+            if let Some(domain) = endpoint.domain() {
+                span.record("domain", tracing::field::display(domain));
+            }
+            span
+        };
+        #[cfg(not(feature = "tracing"))]
+        let _ = endpoint;
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span,
+            start: Instant::now(),
+        }
+    }
+
+    /// Run `f` within the span.
+    #[cfg(feature = "sync")]
+    pub(crate) fn in_scope<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        return f();
+    }
+
+    /// Run `future` within the span.
+    #[cfg(all(feature = "async", feature = "tracing"))]
+    pub(crate) fn instrument<F>(&self, future: F) -> tracing::instrument::Instrumented<F>
+    where
+        F: Future,
+    {
+        tracing::Instrument::instrument(future, self.span.clone())
+    }
+
+    /// Run `future` within the span.
+    #[cfg(all(feature = "async", not(feature = "tracing")))]
+    pub(crate) fn instrument<F>(&self, future: F) -> F
+    where
+        F: Future,
+    {
+        future
+    }
+
+    /// Record the response received from Porkbun, or the failure to get one.
+    pub(crate) fn response<E>(&self, rsp: &Result<Response<Bytes>, E>) {
+        let latency = self.start.elapsed();
+        #[cfg(feature = "tracing")]
+        {
+            if let Ok(rsp) = rsp {
+                self.span.record("http.status", rsp.status().as_u16());
+            }
+            self.span.record("latency_ms", latency.as_millis() as u64);
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = (rsp, latency);
+    }
+
+    /// Record the outcome of the query.
+    pub(crate) fn finish<T, E>(&self, result: Result<T, ApiError<E>>) -> Result<T, ApiError<E>>
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            if let ApiError::PorkBun { status, message } = err {
+                self.span.record("porkbun.status", status.as_str());
+                self.span.record("porkbun.message", message.as_str());
+            }
+            self.span.in_scope(
+                || tracing::debug!(target: "porkbun", error = %err, "porkbun query failed"),
+            );
+        }
+
+        result
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async", feature = "tracing"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use bytes::Bytes;
+    use http::{request::Builder as RequestBuilder, Method, Response, StatusCode};
+    use serde_json::{json, Map, Value};
+    use std::{borrow::Cow, io};
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use tracing_core::span::Current;
+    use url::Url;
+
+    use crate::{
+        api::{self, ApiError, Client, Endpoint, Query},
+        auth::Auth,
+        test::client::{ExpectedUrl, SingleTestClient},
+    };
+
+    /// Collects every span field as `name=value`.
+    #[derive(Clone, Default)]
+    struct Fields {
+        fields: Arc<Mutex<Vec<String>>>,
+        next_id: Arc<AtomicU64>,
+        /// The spans entered, innermost last, for `Span::current`.
+        entered: Arc<Mutex<Vec<(Id, &'static Metadata<'static>)>>>,
+        metadata: Arc<Mutex<HashMap<u64, &'static Metadata<'static>>>>,
+    }
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.fields
+                .lock()
+                .unwrap()
+                .push(format!("{}={:?}", field.name(), value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields
+                .lock()
+                .unwrap()
+                .push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Fields {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut self.clone());
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+            self.metadata.lock().unwrap().insert(id, span.metadata());
+            Id::from_u64(id)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, id: &Id) {
+            let metadata = self.metadata.lock().unwrap()[&id.into_u64()];
+            self.entered.lock().unwrap().push((id.clone(), metadata));
+        }
+
+        fn exit(&self, _: &Id) {
+            self.entered.lock().unwrap().pop();
+        }
+
+        fn current_span(&self) -> Current {
+            match self.entered.lock().unwrap().last() {
+                Some((id, metadata)) => Current::new(id.clone(), metadata),
+                None => Current::none(),
+            }
+        }
+    }
+
+    struct Dummy;
+
+    impl Endpoint for Dummy {
+        fn method(&self) -> Method {
+            Method::POST
+        }
+
+        fn endpoint(&self) -> Cow<'static, str> {
+            "dns/retrieve/example.com".into()
+        }
+
+        fn domain(&self) -> Option<Cow<'_, str>> {
+            Some("example.com".into())
+        }
+
+        fn body(&self) -> Map<String, Value> {
+            let mut body = Map::default();
+            body.insert("secretapikey".into(), "sk1_secret".into());
+            body
+        }
+    }
+
+    #[test]
+    fn span_fields() {
+        let endpoint = ExpectedUrl::builder()
+            .method(Method::POST)
+            .endpoint("dns/retrieve/example.com")
+            .content_type("application/json")
+            .body_json(&json!({
+                "secretapikey": "sk1_secret",
+            }))
+            .status(StatusCode::BAD_REQUEST)
+            .build()
+            .unwrap();
+        let client = SingleTestClient::new_json(
+            endpoint,
+            &json!({
+                "status": "ERROR",
+                "message": "Invalid domain.",
+            }),
+        );
+
+        let subscriber = Fields::default();
+        let fields = subscriber.fields.clone();
+        tracing::subscriber::with_default(subscriber, || {
+            api::ignore(Dummy).query(&client).unwrap_err();
+        });
+
+        let fields = fields.lock().unwrap();
+        for expected in &[
+            "endpoint=dns/retrieve/example.com",
+            "domain=example.com",
+            "attempt=1",
+            "http.status=400",
+            "porkbun.status=ERROR",
+            "porkbun.message=Invalid domain.",
+        ] {
+            assert!(
+                fields.iter().any(|field| field == expected),
+                "missing `{}` in {:?}",
+                expected,
+                fields,
+            );
+        }
+        assert!(fields.iter().any(|field| field.starts_with("latency_ms=")));
+        assert!(!fields.iter().any(|field| field.contains("sk1_secret")));
+    }
+
+    /// A client whose transport always fails, even when retried.
+    struct Unreachable;
+
+    impl Client for Unreachable {
+        type Error = io::Error;
+
+        fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+            Ok(Url::parse("https://porkbun.invalid/api/json/v3/")?.join(endpoint)?)
+        }
+
+        fn rest(
+            &self,
+            _: RequestBuilder,
+            _: Vec<u8>,
+        ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+            api::record_attempt(2);
+            Err(ApiError::client(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "connection refused",
+            )))
+        }
+
+        fn auth(&self) -> Option<Auth> {
+            None
+        }
+    }
+
+    #[test]
+    fn span_fields_transport_error() {
+        let subscriber = Fields::default();
+        let fields = subscriber.fields.clone();
+        tracing::subscriber::with_default(subscriber, || {
+            api::ignore(Dummy).query(&Unreachable).unwrap_err();
+        });
+
+        let fields = fields.lock().unwrap();
+        assert!(fields.iter().any(|field| field.starts_with("latency_ms=")));
+        assert!(!fields.iter().any(|field| field.starts_with("http.status=")));
+        assert!(fields.iter().any(|field| field == "attempt=2"));
+    }
+}
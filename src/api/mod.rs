@@ -1,10 +1,26 @@
+//! The traits tying endpoints to clients.
+//!
+//! [`Endpoint`] describes a request and is always available. [`Client`] and [`Query`] need the
+//! `sync` feature, [`AsyncClient`] and [`AsyncQuery`] the `async` feature.
+
+#[cfg(feature = "async")]
+mod batch;
+#[cfg(any(feature = "sync", feature = "async"))]
 mod client;
 mod endpoint;
 mod error;
 mod ignore;
+#[cfg(any(feature = "sync", feature = "async"))]
+mod instrument;
+#[cfg(any(feature = "sync", feature = "async"))]
 mod query;
 
+#[cfg(feature = "async")]
+pub use self::batch::{Batch, BatchItem, BatchReport};
+
+#[cfg(feature = "async")]
 pub use self::client::AsyncClient;
+#[cfg(feature = "sync")]
 pub use self::client::Client;
 
 pub use self::endpoint::Endpoint;
@@ -12,7 +28,12 @@ pub use self::endpoint::Endpoint;
 pub use self::error::ApiError;
 pub use self::error::BodyError;
 
+#[cfg(any(feature = "sync", feature = "async"))]
+pub use self::instrument::record_attempt;
+
+#[cfg(feature = "async")]
 pub use self::query::AsyncQuery;
+#[cfg(feature = "sync")]
 pub use self::query::Query;
 
 pub use self::ignore::ignore;
@@ -1,8 +1,19 @@
+#[cfg(feature = "async")]
 use async_trait::async_trait;
-use http::Uri;
+use http::{header, request::Builder as RequestBuilder, Request, Uri};
+use log::debug;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::{Map, Value};
+use std::{io, mem};
 use url::Url;
+use zeroize::Zeroizing;
 
-use super::{client::AsyncClient, client::Client, error::ApiError};
+#[cfg(feature = "async")]
+use super::client::AsyncClient;
+#[cfg(feature = "sync")]
+use super::client::Client;
+use super::{endpoint::Endpoint, error::ApiError};
+use crate::auth::{Auth, CREDENTIAL_FIELDS};
 
 pub fn url_to_http_uri(url: Url) -> Uri {
     url.as_str()
@@ -10,7 +21,105 @@ pub fn url_to_http_uri(url: Url) -> Uri {
         .expect("failed to parse a url::Url as an http::Uri")
 }
 
+/// A request body: the fields of an endpoint followed by the credentials.
+///
+/// Serializing this writes the credentials straight into the output rather than through an
+/// intermediate `Map`.
+struct Body<'a> {
+    fields: &'a Map<String, Value>,
+    credentials: Option<[&'a str; 2]>,
+}
+
+impl Serialize for Body<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let fields = self
+            .fields
+            .iter()
+            .filter(|(name, _)| self.credentials.is_none() || !is_credential(name));
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in fields {
+            map.serialize_entry(name, value)?;
+        }
+        if let Some(credentials) = self.credentials {
+            for (name, value) in CREDENTIAL_FIELDS.iter().zip(credentials.iter()) {
+                map.serialize_entry(name, value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+fn is_credential(name: &str) -> bool {
+    CREDENTIAL_FIELDS.contains(&name)
+}
+
+/// Counts the bytes written to it.
+#[derive(Default)]
+struct Length(usize);
+
+impl io::Write for Length {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Build the request for an endpoint, adding the credentials to its body.
+///
+/// The body is the only copy of the credentials made here. It is allocated at its final size
+/// so that growing it does not leave stray copies behind, and wiped if serializing it fails;
+/// wiping it once it has been sent is up to the client.
+pub(crate) fn request<E>(
+    endpoint: &E,
+    url: Url,
+    auth: Option<Auth>,
+) -> Result<(RequestBuilder, Vec<u8>), serde_json::Error>
+where
+    E: Endpoint + ?Sized,
+{
+    let mut req = Request::builder()
+        .method(endpoint.method())
+        .uri(url_to_http_uri(url));
+    let fields = endpoint.body();
+    if fields.is_empty() && auth.is_none() {
+        return Ok((req, vec![]));
+    }
+
+    req = req.header(header::CONTENT_TYPE, "application/json");
+    debug!(target: "porkbun", "request body {}", redacted(&fields, auth.is_some()));
+
+    let body = Body {
+        fields: &fields,
+        credentials: auth
+            .as_ref()
+            .map(|auth| [auth.apikey.expose(), auth.secretapikey.expose()]),
+    };
+    let mut length = Length::default();
+    serde_json::to_writer(&mut length, &body)?;
+    let mut data = Zeroizing::new(Vec::with_capacity(length.0));
+    serde_json::to_writer(&mut *data, &body)?;
+
+    Ok((req, mem::take(&mut *data)))
+}
+
+/// A request body with the credentials masked, fit for logging.
+pub(crate) fn redacted(fields: &Map<String, Value>, credentials: bool) -> String {
+    let body = Body {
+        fields,
+        credentials: Some(["[REDACTED]"; 2]).filter(|_| credentials),
+    };
+    serde_json::to_string(&body).unwrap_or_default()
+}
+
 /// A trait which represents a query which may be made to a Porkbun client.
+#[cfg(feature = "sync")]
 pub trait Query<T, C>
 where
     C: Client,
@@ -20,6 +129,7 @@ where
 }
 
 /// A trait which represents an asynchronous query which may be made to a Porkbun client.
+#[cfg(feature = "async")]
 #[async_trait]
 pub trait AsyncQuery<T, C>
 where
@@ -28,3 +138,110 @@ where
     /// Perform the query asynchronously against the client.
     async fn query_async(&self, client: &C) -> Result<T, ApiError<C::Error>>;
 }
+
+#[cfg(all(test, feature = "client_api"))]
+mod tests {
+    use std::{borrow::Cow, sync::Mutex};
+
+    use http::Method;
+    use log::{LevelFilter, Log, Metadata, Record};
+    use serde_json::{json, Map, Value};
+    use url::Url;
+
+    use crate::{api::Endpoint, auth::Auth};
+
+    static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct CaptureLogger;
+
+    impl Log for CaptureLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            LOGS.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger;
+
+    struct Dummy;
+
+    impl Endpoint for Dummy {
+        fn method(&self) -> Method {
+            Method::POST
+        }
+
+        fn endpoint(&self) -> Cow<'static, str> {
+            "dummy".into()
+        }
+
+        fn body(&self) -> Map<String, Value> {
+            let mut body = Map::default();
+            body.insert("name".into(), "www".into());
+            body
+        }
+    }
+
+    #[test]
+    fn redacted() {
+        let mut body = Dummy.body();
+        body.insert("apikey".into(), "pk1_key".into());
+
+        let redacted = super::redacted(&body, true);
+        assert_eq!(
+            serde_json::from_str::<Value>(&redacted).unwrap(),
+            json!({
+                "name": "www",
+                "apikey": "[REDACTED]",
+                "secretapikey": "[REDACTED]",
+            }),
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&super::redacted(&Dummy.body(), false)).unwrap(),
+            json!({"name": "www"}),
+        );
+    }
+
+    #[test]
+    fn body() {
+        let auth = Auth::new("pk1_key".into(), "sk1_secret".into());
+        let url = Url::parse("https://porkbun.invalid/api/json/v3/dummy").unwrap();
+        let (_, data) = super::request(&Dummy, url, Some(auth)).unwrap();
+
+        assert_eq!(data.len(), data.capacity());
+        assert_eq!(
+            serde_json::from_slice::<Value>(&data).unwrap(),
+            json!({
+                "name": "www",
+                "apikey": "pk1_key",
+                "secretapikey": "sk1_secret",
+            }),
+        );
+    }
+
+    #[test]
+    fn logged_body_is_redacted() {
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(LevelFilter::Debug);
+
+        let auth = Auth::new("pk1_logged_key".into(), "sk1_logged_secret".into());
+        let url = Url::parse("https://porkbun.invalid/api/json/v3/dummy").unwrap();
+        let (_, data) = super::request(&Dummy, url, Some(auth)).unwrap();
+        assert!(String::from_utf8(data)
+            .unwrap()
+            .contains("sk1_logged_secret"));
+
+        let logs = LOGS.lock().unwrap();
+        let body = logs
+            .iter()
+            .find(|line| line.starts_with("request body") && line.contains("www"))
+            .expect("request body was not logged");
+        assert!(body.contains("[REDACTED]"));
+        assert!(!logs.iter().any(|line| line.contains("pk1_logged_key")));
+        assert!(!logs.iter().any(|line| line.contains("sk1_logged_secret")));
+    }
+}
@@ -0,0 +1,402 @@
+#[cfg(feature = "config")]
+use serde::Deserialize;
+use std::{borrow::Cow, env, io, process::ExitStatus};
+#[cfg(feature = "config")]
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+#[cfg(feature = "config")]
+use zeroize::Zeroizing;
+
+mod provider;
+mod secret;
+
+#[cfg(feature = "config")]
+pub use self::provider::FileProvider;
+pub use self::provider::{ChainProvider, CommandProvider, CredentialProvider, EnvProvider};
+pub use self::secret::Secret;
+
+/// The environment variable holding the API key.
+pub const API_KEY_ENV: &str = "PORKBUN_API_KEY";
+/// The environment variable holding the secret API key.
+pub const SECRET_API_KEY_ENV: &str = "PORKBUN_SECRET_API_KEY";
+/// The profile used when none is given explicitly.
+#[cfg(feature = "config")]
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Errors which may occur when loading credentials.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum AuthError {
+    /// A required environment variable is not set.
+    #[error("environment variable `{}` is not set", name)]
+    MissingEnv {
+        /// The name of the variable.
+        name: &'static str,
+    },
+    /// The credentials file could not be read.
+    #[cfg(feature = "config")]
+    #[error("failed to read credentials file {}: {}", path.display(), source)]
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: io::Error,
+    },
+    /// The credentials file may be read by anyone on the machine.
+    #[cfg(feature = "config")]
+    #[error("credentials file {} is world-readable", path.display())]
+    WorldReadable {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// The credentials file is not valid TOML.
+    #[cfg(feature = "config")]
+    #[error("failed to parse credentials file {}: {}", path.display(), source)]
+    Toml {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: toml::de::Error,
+    },
+    /// The credentials file is not valid JSON.
+    #[cfg(feature = "config")]
+    #[error("failed to parse credentials file {}: {}", path.display(), source)]
+    Json {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: serde_json::Error,
+    },
+    /// The requested profile is not in the credentials file.
+    #[cfg(feature = "config")]
+    #[error("profile `{}` not found in {}", profile, path.display())]
+    MissingProfile {
+        /// The requested profile.
+        profile: String,
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// The credentials command could not be started.
+    #[error("failed to run `{}`: {}", program, source)]
+    Command {
+        /// The program that was run.
+        program: String,
+        /// The source of the error.
+        source: io::Error,
+    },
+    /// The credentials command did not exit successfully.
+    #[error("`{}` failed with {}: {}", program, status, stderr)]
+    CommandFailed {
+        /// The program that was run.
+        program: String,
+        /// The exit status of the program.
+        status: ExitStatus,
+        /// What the program wrote to its standard error.
+        stderr: String,
+    },
+    /// The output of the credentials command did not contain credentials.
+    #[error("no credentials found in the output of `{}`", program)]
+    CommandOutput {
+        /// The program that was run.
+        program: String,
+    },
+    /// None of the providers in a chain could find credentials.
+    #[error("no credentials found: {}", join_errors(errors))]
+    NotFound {
+        /// The error of each provider, in order.
+        errors: Vec<AuthError>,
+    },
+}
+
+type AuthResult<T> = Result<T, AuthError>;
+
+fn join_errors(errors: &[AuthError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// A single set of credentials in a credentials file.
+#[cfg(feature = "config")]
+#[derive(Debug, Deserialize)]
+struct Profile {
+    apikey: Secret,
+    secretapikey: Secret,
+}
+
+/// The names of the body fields carrying the credentials.
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) const CREDENTIAL_FIELDS: [&str; 2] = ["apikey", "secretapikey"];
+
+/// The credentials of a Porkbun account.
+///
+/// Both keys are [`Secret`]s, wiped when the last copy is dropped. When a request is sent they
+/// are written into its body, which `UreqPorkbun` wipes after sending it; `Porkbun`,
+/// `AsyncPorkbun` and `HyperPorkbun` hand the body over to their HTTP library, whose buffers
+/// are not wiped.
+#[derive(Debug, Clone)]
+pub struct Auth {
+    pub apikey: Secret,
+    pub secretapikey: Secret,
+}
+
+impl Auth {
+    pub fn new(key: Cow<'static, str>, secret: Cow<'static, str>) -> Self {
+        Self {
+            apikey: key.into(),
+            secretapikey: secret.into(),
+        }
+    }
+
+    /// Read the credentials from `PORKBUN_API_KEY` and `PORKBUN_SECRET_API_KEY`.
+    pub fn from_env() -> AuthResult<Self> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars<F>(var: F) -> AuthResult<Self>
+    where
+        F: Fn(&'static str) -> Option<String>,
+    {
+        let lookup = |name| {
+            var(name)
+                .filter(|value| !value.is_empty())
+                .ok_or(AuthError::MissingEnv { name })
+        };
+
+        Ok(Self::new(
+            lookup(API_KEY_ENV)?.into(),
+            lookup(SECRET_API_KEY_ENV)?.into(),
+        ))
+    }
+
+    /// Read the credentials of `profile` from a credentials file.
+    ///
+    /// Files ending in `.json` are parsed as JSON, anything else as TOML. Either way the file
+    /// maps profile names to an `apikey` and a `secretapikey`:
+    ///
+    /// ```toml
+    /// [default]
+    /// apikey = "pk1_..."
+    /// secretapikey = "sk1_..."
+    /// ```
+    ///
+    /// On Unix, files which are readable by everyone are refused.
+    #[cfg(feature = "config")]
+    pub fn from_file<P>(path: P, profile: &str) -> AuthResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let io_err = |source| AuthError::Io {
+            path: path.into(),
+            source,
+        };
+
+        let mut file = File::open(path).map_err(io_err)?;
+        check_permissions(path, &file)?;
+        let mut contents = Zeroizing::new(String::new());
+        file.read_to_string(&mut contents).map_err(io_err)?;
+
+        let mut profiles: HashMap<String, Profile> = if path.extension() == Some(OsStr::new("json"))
+        {
+            serde_json::from_str(&contents).map_err(|source| AuthError::Json {
+                path: path.into(),
+                source,
+            })?
+        } else {
+            toml::from_str(&contents).map_err(|source| AuthError::Toml {
+                path: path.into(),
+                source,
+            })?
+        };
+
+        let profile = profiles
+            .remove(profile)
+            .ok_or_else(|| AuthError::MissingProfile {
+                profile: profile.into(),
+                path: path.into(),
+            })?;
+
+        Ok(Self {
+            apikey: profile.apikey,
+            secretapikey: profile.secretapikey,
+        })
+    }
+}
+
+#[cfg(all(feature = "config", unix))]
+fn check_permissions(path: &Path, file: &File) -> AuthResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = file.metadata().map_err(|source| AuthError::Io {
+        path: path.into(),
+        source,
+    })?;
+    if metadata.permissions().mode() & 0o004 != 0 {
+        return Err(AuthError::WorldReadable { path: path.into() });
+    }
+
+    Ok(())
+}
+
+#[cfg(all(feature = "config", not(unix)))]
+fn check_permissions(_: &Path, _: &File) -> AuthResult<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    #[cfg(feature = "config")]
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    #[cfg(feature = "config")]
+    use super::Profile;
+    use super::{Auth, AuthError, API_KEY_ENV, SECRET_API_KEY_ENV};
+
+    /// A credentials file which is removed again at the end of the test.
+    #[cfg(feature = "config")]
+    struct CredentialsFile(PathBuf);
+
+    #[cfg(feature = "config")]
+    impl AsRef<Path> for CredentialsFile {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    #[cfg(feature = "config")]
+    impl Drop for CredentialsFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+            if let Some(dir) = self.0.parent() {
+                // Fails while other tests still have files in it.
+                let _ = fs::remove_dir(dir);
+            }
+        }
+    }
+
+    #[cfg(feature = "config")]
+    fn credentials_file(name: &str, contents: &str, mode: u32) -> CredentialsFile {
+        let dir = std::env::temp_dir().join(format!("porkbun-rs-auth-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        CredentialsFile(path)
+    }
+
+    #[test]
+    fn from_vars() {
+        let vars: HashMap<_, _> = vec![(API_KEY_ENV, "key"), (SECRET_API_KEY_ENV, "secret")]
+            .into_iter()
+            .collect();
+
+        let auth = Auth::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(auth.apikey.expose(), "key");
+        assert_eq!(auth.secretapikey.expose(), "secret");
+    }
+
+    #[test]
+    fn from_vars_missing_secret() {
+        let err = Auth::from_vars(|name| match name {
+            API_KEY_ENV => Some("key".into()),
+            _ => Some("".into()),
+        })
+        .err()
+        .unwrap();
+        if let AuthError::MissingEnv { name } = err {
+            assert_eq!(name, SECRET_API_KEY_ENV);
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn from_toml_file() {
+        let path = credentials_file(
+            "credentials.toml",
+            "[default]\napikey = \"k1\"\nsecretapikey = \"s1\"\n\n[ci]\napikey = \"k2\"\nsecretapikey = \"s2\"\n",
+            0o600,
+        );
+
+        let auth = Auth::from_file(&path, "ci").unwrap();
+        assert_eq!(auth.apikey.expose(), "k2");
+        assert_eq!(auth.secretapikey.expose(), "s2");
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn from_json_file() {
+        let path = credentials_file(
+            "credentials.json",
+            r#"{"default": {"apikey": "k1", "secretapikey": "s1"}}"#,
+            0o600,
+        );
+
+        let auth = Auth::from_file(&path, "default").unwrap();
+        assert_eq!(auth.apikey.expose(), "k1");
+        assert_eq!(auth.secretapikey.expose(), "s1");
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn from_file_missing_profile() {
+        let path = credentials_file(
+            "missing.toml",
+            "[default]\napikey = \"k1\"\nsecretapikey = \"s1\"\n",
+            0o600,
+        );
+
+        let err = Auth::from_file(&path, "work").err().unwrap();
+        if let AuthError::MissingProfile { profile, .. } = err {
+            assert_eq!(profile, "work");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn redacted_profile() {
+        let profile: Profile =
+            serde_json::from_str(r#"{"apikey": "pk1_key", "secretapikey": "sk1_secret"}"#).unwrap();
+        let debug = format!("{:?}", profile);
+        assert!(!debug.contains("pk1_key"));
+        assert!(!debug.contains("sk1_secret"));
+    }
+
+    #[cfg(all(feature = "config", unix))]
+    #[test]
+    fn from_file_world_readable() {
+        let path = credentials_file(
+            "readable.toml",
+            "[default]\napikey = \"k1\"\nsecretapikey = \"s1\"\n",
+            0o644,
+        );
+
+        let err = Auth::from_file(&path, "default").err().unwrap();
+        if let AuthError::WorldReadable { .. } = err {
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+}
@@ -0,0 +1,303 @@
+#[cfg(feature = "config")]
+use super::DEFAULT_PROFILE;
+use super::{Auth, AuthError, AuthResult};
+#[cfg(feature = "config")]
+use std::{env, path::PathBuf};
+use std::{
+    ffi::OsString,
+    process::{Command, Stdio},
+};
+use zeroize::Zeroizing;
+
+/// A source of Porkbun credentials.
+pub trait CredentialProvider {
+    /// Load the credentials.
+    fn credentials(&self) -> AuthResult<Auth>;
+}
+
+impl<P> CredentialProvider for Box<P>
+where
+    P: CredentialProvider + ?Sized,
+{
+    fn credentials(&self) -> AuthResult<Auth> {
+        (**self).credentials()
+    }
+}
+
+/// Credentials from the `PORKBUN_API_KEY` and `PORKBUN_SECRET_API_KEY` environment variables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvProvider;
+
+impl CredentialProvider for EnvProvider {
+    fn credentials(&self) -> AuthResult<Auth> {
+        Auth::from_env()
+    }
+}
+
+/// Credentials from a profile in a credentials file.
+#[cfg(feature = "config")]
+#[derive(Debug, Clone)]
+pub struct FileProvider {
+    path: PathBuf,
+    profile: String,
+}
+
+#[cfg(feature = "config")]
+impl FileProvider {
+    /// Read the default profile of the file at `path`.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            path: path.into(),
+            profile: DEFAULT_PROFILE.into(),
+        }
+    }
+
+    /// The conventional credentials file, `$XDG_CONFIG_HOME/porkbun/credentials.toml` or
+    /// `~/.config/porkbun/credentials.toml`, if the home directory is known.
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(dir.join("porkbun").join("credentials.toml"))
+    }
+
+    /// Read another profile than the default one.
+    pub fn profile<S>(mut self, profile: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.profile = profile.into();
+        self
+    }
+}
+
+#[cfg(feature = "config")]
+impl CredentialProvider for FileProvider {
+    fn credentials(&self) -> AuthResult<Auth> {
+        Auth::from_file(&self.path, &self.profile)
+    }
+}
+
+/// Credentials from the output of an external command such as `pass show porkbun`.
+///
+/// The output is scanned for `apikey` and `secretapikey` entries written as `key: value` or
+/// `key=value` (`PORKBUN_API_KEY` and `PORKBUN_SECRET_API_KEY` are accepted as well). If there
+/// are none, the first two non-empty lines are taken as the API key and the secret API key;
+/// output labelling only one of them is rejected.
+#[derive(Debug, Clone)]
+pub struct CommandProvider {
+    program: OsString,
+    args: Vec<OsString>,
+}
+
+impl CommandProvider {
+    /// Run `program` without arguments.
+    pub fn new<S>(program: S) -> Self
+    where
+        S: Into<OsString>,
+    {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Add an argument to the command.
+    pub fn arg<S>(mut self, arg: S) -> Self
+    where
+        S: Into<OsString>,
+    {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Add arguments to the command.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    fn program_name(&self) -> String {
+        self.program.to_string_lossy().into_owned()
+    }
+}
+
+impl CredentialProvider for CommandProvider {
+    fn credentials(&self) -> AuthResult<Auth> {
+        let output = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|source| AuthError::Command {
+                program: self.program_name(),
+                source,
+            })?;
+
+        if !output.status.success() {
+            return Err(AuthError::CommandFailed {
+                program: self.program_name(),
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().into(),
+            });
+        }
+
+        // The output holds the credentials: keep it, and any lossy copy, in wiped buffers.
+        let stdout = Zeroizing::new(output.stdout);
+        let stdout = Zeroizing::new(String::from_utf8_lossy(&stdout).into_owned());
+        parse_command_output(&stdout).ok_or_else(|| AuthError::CommandOutput {
+            program: self.program_name(),
+        })
+    }
+}
+
+fn parse_command_output(output: &str) -> Option<Auth> {
+    let lines = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut key = None;
+    let mut secret = None;
+    for line in &lines {
+        let (name, value) = match line.find([':', '=']) {
+            Some(idx) => (&line[..idx], line[idx + 1..].trim()),
+            None => continue,
+        };
+        let name = name
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match name.trim_start_matches("porkbun") {
+            "apikey" => key = Some(value),
+            "secretapikey" => secret = Some(value),
+            _ => (),
+        }
+    }
+
+    // Only fall back to the bare lines when nothing is labelled; a lone label means the output
+    // is not in the shape we expect.
+    let (key, secret) = match (key, secret) {
+        (Some(key), Some(secret)) => (key, secret),
+        (None, None) => match lines.as_slice() {
+            [key, secret, ..] => (*key, *secret),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(Auth::new(key.to_string().into(), secret.to_string().into()))
+}
+
+/// Try several providers in order and use the first one which finds credentials.
+#[derive(Default)]
+pub struct ChainProvider {
+    providers: Vec<Box<dyn CredentialProvider + Send + Sync>>,
+}
+
+impl ChainProvider {
+    /// Create an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a provider at the end of the chain.
+    pub fn with<P>(mut self, provider: P) -> Self
+    where
+        P: CredentialProvider + Send + Sync + 'static,
+    {
+        self.providers.push(Box::new(provider));
+        self
+    }
+}
+
+impl CredentialProvider for ChainProvider {
+    fn credentials(&self) -> AuthResult<Auth> {
+        let mut errors = Vec::new();
+        for provider in &self.providers {
+            match provider.credentials() {
+                Ok(auth) => return Ok(auth),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        Err(AuthError::NotFound { errors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::{AuthError, CredentialProvider};
+
+    use super::{parse_command_output, ChainProvider, CommandProvider};
+
+    #[test]
+    fn command_output_key_values() {
+        let auth = parse_command_output(
+            "hunter2\nsecretapikey: sk1_secret\nPORKBUN_API_KEY=pk1_key\nurl: porkbun.com\n",
+        )
+        .unwrap();
+        assert_eq!(auth.apikey.expose(), "pk1_key");
+        assert_eq!(auth.secretapikey.expose(), "sk1_secret");
+    }
+
+    #[test]
+    fn command_output_lines() {
+        let auth = parse_command_output("\npk1_key\nsk1_secret\n").unwrap();
+        assert_eq!(auth.apikey.expose(), "pk1_key");
+        assert_eq!(auth.secretapikey.expose(), "sk1_secret");
+    }
+
+    #[test]
+    fn command_output_insufficient() {
+        assert!(parse_command_output("pk1_key\n").is_none());
+    }
+
+    #[test]
+    fn command_output_partly_labelled() {
+        assert!(parse_command_output("hunter2\nsecretapikey: sk1_secret\n").is_none());
+        assert!(parse_command_output("apikey: pk1_key\nsk1_secret\n").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_provider() {
+        let auth = CommandProvider::new("printf")
+            .arg("apikey: pk1_key\\nsecretapikey: sk1_secret\\n")
+            .credentials()
+            .unwrap();
+        assert_eq!(auth.apikey.expose(), "pk1_key");
+        assert_eq!(auth.secretapikey.expose(), "sk1_secret");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn chain_provider_falls_through() {
+        let auth = ChainProvider::new()
+            .with(CommandProvider::new("false"))
+            .with(CommandProvider::new("printf").arg("pk1_key\\nsk1_secret\\n"))
+            .credentials()
+            .unwrap();
+        assert_eq!(auth.apikey.expose(), "pk1_key");
+        assert_eq!(auth.secretapikey.expose(), "sk1_secret");
+    }
+
+    #[test]
+    fn chain_provider_reports_every_error() {
+        let err = ChainProvider::new().credentials().err().unwrap();
+        if let AuthError::NotFound { errors } = err {
+            assert!(errors.is_empty());
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+}
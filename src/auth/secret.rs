@@ -0,0 +1,99 @@
+use serde::{Deserialize, Deserializer};
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug, Display},
+};
+use zeroize::Zeroize;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// A credential which is never shown by `Debug` or `Display` and is wiped from memory when
+/// dropped.
+///
+/// Only the secret's own copy is wiped: a `String` is moved in as is, but wrapping a borrowed
+/// `&str` copies it and leaves the original to its owner.
+#[derive(Clone, Default)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap a credential.
+    pub fn new<S>(secret: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(secret.into())
+    }
+
+    /// The credential itself, for sending it to Porkbun.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Secret").field(&REDACTED).finish()
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.into())
+    }
+}
+
+impl From<Cow<'_, str>> for Secret {
+    fn from(secret: Cow<'_, str>) -> Self {
+        Self(secret.into_owned())
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Secret;
+
+    #[test]
+    fn redacted_debug() {
+        let secret = Secret::new("sk1_secret");
+        assert_eq!(format!("{:?}", secret), "Secret(\"[REDACTED]\")");
+    }
+
+    #[test]
+    fn redacted_display() {
+        let secret = Secret::new("sk1_secret");
+        assert_eq!(secret.to_string(), "[REDACTED]");
+    }
+
+    #[test]
+    fn expose() {
+        let secret = Secret::new("sk1_secret");
+        assert_eq!(secret.expose(), "sk1_secret");
+    }
+}
@@ -0,0 +1,617 @@
+//! Back up the records of every domain of the account, and restore them.
+//!
+//! [`Backup`] lists the domains of the account and retrieves their records concurrently into a
+//! [`Snapshot`], which is saved as JSON. [`Restore`] recreates the records of a snapshot that
+//! domains no longer have. It never edits nor deletes anything: a record whose name and type
+//! are now used with other content is reported as a conflict instead.
+
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{
+    api::{ApiError, AsyncClient, AsyncQuery},
+    endpoints::{DnsContentError, ListAllDomains, RetrieveDns, LIST_ALL_PAGE_SIZE},
+    sync::DesiredRecord,
+    CreateResponse, Domain, ListAllResponse, Record, RetrieveResponse,
+};
+
+/// The version of the snapshot format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// How many domains are retrieved at once by default.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Errors which may occur while reading or writing a snapshot.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum SnapshotError {
+    /// The file could not be read or written.
+    #[error("failed to access {}: {}", path.display(), source)]
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: io::Error,
+    },
+    /// The file is not a valid snapshot.
+    #[error("invalid snapshot {}: {}", path.display(), source)]
+    Json {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: serde_json::Error,
+    },
+    /// The snapshot was written in a format this version does not know.
+    #[error("unsupported snapshot version {} in {}", version, path.display())]
+    Version {
+        /// The path of the file.
+        path: PathBuf,
+        /// The version of the snapshot.
+        version: u32,
+    },
+}
+
+/// Errors which may occur while taking a snapshot.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum BackupError<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// The domains could not be listed.
+    #[error("failed to list the domains: {}", source)]
+    List {
+        /// The source of the error.
+        source: ApiError<E>,
+    },
+    /// The records of a domain could not be retrieved.
+    #[error("failed to retrieve the records of {}: {}", domain, source)]
+    Retrieve {
+        /// The domain.
+        domain: String,
+        /// The source of the error.
+        source: ApiError<E>,
+    },
+}
+
+/// The records of some domains at some point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The version of the format, [`SNAPSHOT_VERSION`] when taken by this version.
+    pub version: u32,
+    /// When the snapshot was taken.
+    pub created: DateTime<Utc>,
+    /// The records of each domain.
+    pub domains: BTreeMap<String, Vec<Record>>,
+}
+
+impl Snapshot {
+    /// A snapshot of these records, taken now.
+    pub fn new(domains: BTreeMap<String, Vec<Record>>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            created: Utc::now(),
+            domains,
+        }
+    }
+
+    /// Read a snapshot saved with [`Snapshot::save`].
+    pub fn load<P>(path: P) -> Result<Self, SnapshotError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = fs::read(path).map_err(|source| SnapshotError::Io {
+            path: path.into(),
+            source,
+        })?;
+
+        // Look at the version first, as other versions may not have the same fields.
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let json = |source| SnapshotError::Json {
+            path: path.into(),
+            source,
+        };
+        let Version { version } = serde_json::from_slice(&contents).map_err(json)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version {
+                path: path.into(),
+                version,
+            });
+        }
+        serde_json::from_slice(&contents).map_err(json)
+    }
+
+    /// Write the snapshot as JSON.
+    pub fn save<P>(&self, path: P) -> Result<(), SnapshotError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = serde_json::to_vec_pretty(self).map_err(|source| SnapshotError::Json {
+            path: path.into(),
+            source,
+        })?;
+
+        // Write a sibling file first so an interrupted write never leaves a truncated snapshot.
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, contents)
+            .and_then(|()| fs::rename(&tmp, path))
+            .map_err(|source| SnapshotError::Io {
+                path: path.into(),
+                source,
+            })
+    }
+}
+
+/// List every domain of the account.
+pub async fn list_domains<C>(client: &C) -> Result<Vec<Domain>, ApiError<C::Error>>
+where
+    C: AsyncClient + Sync,
+{
+    let mut domains = Vec::new();
+    loop {
+        let rsp: ListAllResponse = ListAllDomains::builder()
+            .start(domains.len())
+            .build()
+            .unwrap()
+            .query_async(client)
+            .await?;
+        let count = rsp.domains.len();
+        domains.extend(rsp.domains);
+        if count < LIST_ALL_PAGE_SIZE {
+            return Ok(domains);
+        }
+    }
+}
+
+/// Takes snapshots of every domain of the account.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    concurrency: usize,
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl Backup {
+    /// Retrieve 4 domains at once.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retrieve `concurrency` domains at once, at least one.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Take a snapshot of the records of every domain.
+    ///
+    /// The first failure aborts the backup, as a partial snapshot would look like the records
+    /// of the missing domains were all deleted.
+    pub async fn run<C>(&self, client: &C) -> Result<Snapshot, BackupError<C::Error>>
+    where
+        C: AsyncClient + Sync,
+    {
+        let domains = list_domains(client)
+            .await
+            .map_err(|source| BackupError::List { source })?;
+        info!(target: "porkbun", "backing up {} domains", domains.len());
+
+        let domains = stream::iter(domains)
+            .map(|Domain { domain, .. }| async move {
+                let rsp = match RetrieveDns::builder().domain(domain.as_str()).build() {
+                    Ok(endpoint) => endpoint.query_async(client).await,
+                    Err(err) => Err(ApiError::endpoint(err)),
+                };
+                let rsp: RetrieveResponse = rsp.map_err(|source| BackupError::Retrieve {
+                    domain: domain.clone(),
+                    source,
+                })?;
+                Ok((domain.to_lowercase(), rsp.records))
+            })
+            .buffer_unordered(self.concurrency)
+            .try_collect()
+            .await?;
+        Ok(Snapshot::new(domains))
+    }
+}
+
+/// What became of a record of a snapshot.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Outcome<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// The domain still has the record.
+    Unchanged,
+    /// The record was created again.
+    Created {
+        /// The id of the new record.
+        id: String,
+    },
+    /// The domain has other records with this name and type, so it was not created.
+    Conflict {
+        /// The records with this name and type which are not in the snapshot.
+        current: Vec<Record>,
+    },
+    /// The record cannot be created through the API.
+    Unsupported {
+        /// The source of the error.
+        source: DnsContentError,
+    },
+    /// Creating the record failed.
+    Failed {
+        /// The source of the error.
+        source: ApiError<E>,
+    },
+}
+
+/// A record of a snapshot and what became of it.
+#[derive(Debug)]
+pub struct Restored<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// The domain of the record.
+    pub domain: String,
+    /// The record, as in the snapshot.
+    pub record: Record,
+    /// What became of it.
+    pub outcome: Outcome<E>,
+}
+
+/// What a restore did.
+#[derive(Debug)]
+pub struct RestoreReport<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// Every record of the restored domains, in the order of the snapshot.
+    pub records: Vec<Restored<E>>,
+    /// The domains whose records could not be retrieved, which were skipped.
+    pub errors: Vec<(String, ApiError<E>)>,
+}
+
+impl<E> RestoreReport<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// The records which were created again.
+    pub fn created(&self) -> impl Iterator<Item = &Restored<E>> {
+        self.records
+            .iter()
+            .filter(|restored| matches!(restored.outcome, Outcome::Created { .. }))
+    }
+
+    /// The records which were not created because of other records.
+    pub fn conflicts(&self) -> impl Iterator<Item = &Restored<E>> {
+        self.records
+            .iter()
+            .filter(|restored| matches!(restored.outcome, Outcome::Conflict { .. }))
+    }
+
+    /// Whether every record of the restored domains is now there.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+            && self.records.iter().all(|restored| {
+                matches!(
+                    restored.outcome,
+                    Outcome::Unchanged | Outcome::Created { .. }
+                )
+            })
+    }
+}
+
+/// Recreates the records of a snapshot.
+#[derive(Debug, Clone)]
+pub struct Restore<'a> {
+    snapshot: &'a Snapshot,
+    domains: Option<BTreeSet<String>>,
+}
+
+impl<'a> Restore<'a> {
+    /// Restore every domain of `snapshot`.
+    pub fn new(snapshot: &'a Snapshot) -> Self {
+        Self {
+            snapshot,
+            domains: None,
+        }
+    }
+
+    /// Only restore these domains; those which are not in the snapshot are ignored.
+    pub fn with_domains<I, S>(mut self, domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let domains = domains
+            .into_iter()
+            .map(|domain| domain.as_ref().trim_end_matches('.').to_lowercase());
+        self.domains = Some(domains.collect());
+        self
+    }
+
+    /// Create the records of the snapshot which the domains no longer have.
+    ///
+    /// Domains are restored one after the other, and failures do not stop the restore.
+    pub async fn run<C>(&self, client: &C) -> RestoreReport<C::Error>
+    where
+        C: AsyncClient + Sync,
+    {
+        let mut report = RestoreReport {
+            records: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        for (domain, records) in &self.snapshot.domains {
+            if let Some(domains) = &self.domains {
+                if !domains.contains(&domain.to_lowercase()) {
+                    continue;
+                }
+            }
+
+            let rsp: Result<RetrieveResponse, _> =
+                match RetrieveDns::builder().domain(domain.as_str()).build() {
+                    Ok(endpoint) => endpoint.query_async(client).await,
+                    Err(err) => Err(ApiError::endpoint(err)),
+                };
+            match rsp {
+                Ok(rsp) => {
+                    for record in records {
+                        let outcome = restore(client, domain, records, &rsp.records, record).await;
+                        report.records.push(Restored {
+                            domain: domain.clone(),
+                            record: record.clone(),
+                            outcome,
+                        });
+                    }
+                }
+                Err(err) => {
+                    warn!(target: "porkbun", "failed to retrieve the records of {}: {}", domain, err);
+                    report.errors.push((domain.clone(), err));
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// Restore `record`, one of the `saved` records of `domain` which has `current` records.
+async fn restore<C>(
+    client: &C,
+    domain: &str,
+    saved: &[Record],
+    current: &[Record],
+    record: &Record,
+) -> Outcome<C::Error>
+where
+    C: AsyncClient + Sync,
+{
+    let desired = match DesiredRecord::from_record(domain, record) {
+        Ok(desired) => desired,
+        Err(source) => {
+            warn!(
+                target: "porkbun",
+                "cannot restore the {} record of {}: {}",
+                record.record_type, record.name, source
+            );
+            return Outcome::Unsupported { source };
+        }
+    };
+
+    let name = desired.fqdn(&domain.to_lowercase());
+    let record_type = desired.record.record_type();
+    let same_key = |other: &&Record| {
+        other.name.trim_end_matches('.').eq_ignore_ascii_case(&name)
+            && other.record_type.eq_ignore_ascii_case(record_type)
+    };
+
+    let current = current.iter().filter(same_key).collect::<Vec<_>>();
+    if current.iter().any(|other| desired.same_content(other)) {
+        return Outcome::Unchanged;
+    }
+
+    // Records with this name and type are only in the way if the snapshot does not have them.
+    let saved = saved
+        .iter()
+        .filter(same_key)
+        .filter_map(|saved| DesiredRecord::from_record(domain, saved).ok())
+        .collect::<Vec<_>>();
+    let others = current
+        .into_iter()
+        .filter(|other| !saved.iter().any(|saved| saved.same_content(other)))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !others.is_empty() {
+        warn!(
+            target: "porkbun",
+            "not restoring the {} record of {}: it now has other content",
+            record_type, name
+        );
+        return Outcome::Conflict { current: others };
+    }
+
+    let rsp: Result<CreateResponse, _> = match desired.create(domain) {
+        Ok(endpoint) => endpoint.query_async(client).await,
+        Err(err) => Err(ApiError::endpoint(err)),
+    };
+    match rsp {
+        Ok(rsp) => {
+            info!(target: "porkbun", "restored the {} record of {}", record_type, name);
+            Outcome::Created { id: rsp.id }
+        }
+        Err(source) => {
+            warn!(
+                target: "porkbun",
+                "failed to restore the {} record of {}: {}",
+                record_type, name, source
+            );
+            Outcome::Failed { source }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use std::{collections::BTreeMap, fs};
+
+    use crate::{
+        api::Query,
+        endpoints::{CreateDns, DnsContent},
+        test::FakePorkbun,
+        CreateResponse, Record,
+    };
+
+    use super::{Backup, Outcome, Restore, Snapshot, SnapshotError, SNAPSHOT_VERSION};
+
+    fn create(fake: &FakePorkbun, domain: &str, name: &str, record: DnsContent) -> String {
+        let rsp: CreateResponse = CreateDns::builder()
+            .domain(domain)
+            .name(name)
+            .record(record)
+            .build()
+            .unwrap()
+            .query(fake)
+            .unwrap();
+        rsp.id
+    }
+
+    fn a(content: &str) -> DnsContent {
+        DnsContent::A {
+            content: content.parse().unwrap(),
+        }
+    }
+
+    fn fake() -> FakePorkbun {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_domain("example.org");
+        create(&fake, "example.com", "www", a("192.0.2.1"));
+        create(
+            &fake,
+            "example.com",
+            "",
+            DnsContent::Mx {
+                content: "mail.example.com".into(),
+                priority: 10,
+            },
+        );
+        create(&fake, "example.org", "", a("192.0.2.2"));
+        fake
+    }
+
+    #[tokio::test]
+    async fn backup() {
+        let fake = fake();
+        let snapshot = Backup::new().with_concurrency(2).run(&fake).await.unwrap();
+
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert_eq!(
+            snapshot.domains.keys().collect::<Vec<_>>(),
+            ["example.com", "example.org"]
+        );
+        assert_eq!(snapshot.domains["example.com"].len(), 2);
+        assert_eq!(snapshot.domains["example.org"][0].content, "192.0.2.2");
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("porkbun-backup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("snapshot.json");
+
+        let snapshot = Snapshot::new(BTreeMap::from([(
+            "example.com".to_string(),
+            fake().records("example.com").unwrap(),
+        )]));
+        snapshot.save(&path).unwrap();
+        let loaded = Snapshot::load(&path).unwrap();
+        assert_eq!(loaded.created, snapshot.created);
+        assert_eq!(loaded.domains["example.com"].len(), 2);
+
+        fs::write(&path, r#"{"version": 2, "records": []}"#).unwrap();
+        let err = Snapshot::load(&path).unwrap_err();
+        assert!(matches!(err, SnapshotError::Version { version: 2, .. }));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn restore() {
+        let fake = fake();
+        let mut snapshot = Backup::new().run(&fake).await.unwrap();
+        snapshot
+            .domains
+            .get_mut("example.com")
+            .unwrap()
+            .push(Record {
+                id: "1".into(),
+                name: "example.com".into(),
+                record_type: "ALIAS".into(),
+                content: "example.net".into(),
+                ttl: "600".into(),
+                priority: None,
+                notes: None,
+            });
+
+        // Lose everything, and reuse the name of a lost record for something else.
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_domain("example.org");
+        create(&fake, "example.com", "www", a("192.0.2.9"));
+
+        let report = Restore::new(&snapshot)
+            .with_domains(["Example.com."])
+            .run(&fake)
+            .await;
+        let outcomes = report
+            .records
+            .iter()
+            .map(|restored| match &restored.outcome {
+                Outcome::Unchanged => "unchanged",
+                Outcome::Created { .. } => "created",
+                Outcome::Conflict { .. } => "conflict",
+                Outcome::Unsupported { .. } => "unsupported",
+                Outcome::Failed { .. } => "failed",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(outcomes, ["conflict", "created", "unsupported"]);
+        assert!(!report.is_complete());
+        if let Outcome::Conflict { current } = &report.conflicts().next().unwrap().outcome {
+            assert_eq!(current[0].content, "192.0.2.9");
+        }
+
+        let records = fake.records("example.com").unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].record_type, "MX");
+        assert_eq!(records[1].priority.as_deref(), Some("10"));
+        assert!(fake.records("example.org").unwrap().is_empty());
+
+        // Only the other domain is left to restore.
+        let report = Restore::new(&snapshot).run(&fake).await;
+        let created = report.created().collect::<Vec<_>>();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].domain, "example.org");
+        assert_eq!(report.conflicts().count(), 1);
+    }
+}
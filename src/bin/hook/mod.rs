@@ -0,0 +1,46 @@
+//! What the ACME hook binaries share.
+//!
+//! Credentials are read from `PORKBUN_API_KEY` and `PORKBUN_SECRET_API_KEY`, falling back to
+//! the default profile of `$XDG_CONFIG_HOME/porkbun/credentials.toml`.
+
+use porkbun_rs::{
+    acme::Solver,
+    auth::{ChainProvider, CredentialProvider, EnvProvider, FileProvider},
+    Porkbun,
+};
+use std::{env, error::Error, process, time::Duration};
+use url::Url;
+
+/// How long to wait for Porkbun to list a new record.
+const LISTING_TIMEOUT: Duration = Duration::from_secs(120);
+const LISTING_INTERVAL: Duration = Duration::from_secs(2);
+
+pub fn solver() -> Result<Solver<Porkbun>, Box<dyn Error>> {
+    let mut provider = ChainProvider::new().with(EnvProvider);
+    if let Some(path) = FileProvider::default_path() {
+        provider = provider.with(FileProvider::new(path));
+    }
+
+    let mut client = Porkbun::new(provider.credentials()?)?;
+    // For testing against something else than Porkbun.
+    if let Some(url) = env::var_os("PORKBUN_API_URL") {
+        client = client.with_url(Url::parse(&url.to_string_lossy())?);
+    }
+
+    Ok(Solver::new(client).with_wait(LISTING_TIMEOUT, LISTING_INTERVAL))
+}
+
+/// A required environment variable.
+// Only the certbot hooks are configured through the environment.
+#[allow(dead_code)]
+pub fn var(name: &str) -> Result<String, Box<dyn Error>> {
+    env::var(name).map_err(|_| format!("environment variable `{}` is not set", name).into())
+}
+
+/// Exit with a message on errors.
+pub fn exit(name: &str, result: Result<(), Box<dyn Error>>) {
+    if let Err(err) = result {
+        eprintln!("{}: {}", name, err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,29 @@
+//! A certbot `--manual-auth-hook` creating the `dns-01` challenge record.
+//!
+//! Certbot does not check that the record is served before asking for validation, so this
+//! waits `PORKBUN_PROPAGATION_SECONDS` (60 by default) once Porkbun lists it.
+
+mod hook;
+
+use std::{error::Error, thread, time::Duration};
+
+const DEFAULT_PROPAGATION_SECONDS: u64 = 60;
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let fqdn = hook::var("CERTBOT_DOMAIN")?;
+    let validation = hook::var("CERTBOT_VALIDATION")?;
+    let propagation = match std::env::var("PORKBUN_PROPAGATION_SECONDS") {
+        Ok(seconds) => seconds
+            .parse()
+            .map_err(|_| format!("invalid PORKBUN_PROPAGATION_SECONDS `{}`", seconds))?,
+        Err(_) => DEFAULT_PROPAGATION_SECONDS,
+    };
+
+    hook::solver()?.present_fqdn(&fqdn, &validation)?;
+    thread::sleep(Duration::from_secs(propagation));
+    Ok(())
+}
+
+fn main() {
+    hook::exit("porkbun-certbot-auth", run());
+}
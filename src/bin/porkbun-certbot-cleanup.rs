@@ -0,0 +1,16 @@
+//! A certbot `--manual-cleanup-hook` removing the `dns-01` challenge record.
+
+mod hook;
+
+use std::error::Error;
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let fqdn = hook::var("CERTBOT_DOMAIN")?;
+    let validation = hook::var("CERTBOT_VALIDATION")?;
+    hook::solver()?.cleanup_fqdn(&fqdn, &validation)?;
+    Ok(())
+}
+
+fn main() {
+    hook::exit("porkbun-certbot-cleanup", run());
+}
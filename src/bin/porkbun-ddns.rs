@@ -0,0 +1,82 @@
+//! Keep Porkbun `A`/`AAAA` records pointing at the public address of this host.
+//!
+//! Credentials are read from `PORKBUN_API_KEY` and `PORKBUN_SECRET_API_KEY`, falling back to a
+//! profile of `$XDG_CONFIG_HOME/porkbun/credentials.toml` (or the file given with
+//! `--credentials`). `SIGINT` and `SIGTERM` stop the daemon between two updates.
+
+use clap::Parser;
+use porkbun_rs::{
+    auth::{ChainProvider, CredentialProvider, EnvProvider, FileProvider},
+    ddns::{Action, Config, Updater},
+    Porkbun,
+};
+use std::{error::Error, path::PathBuf, process, sync::mpsc, time::Duration};
+use url::Url;
+
+#[derive(Debug, Parser)]
+#[command(name = "porkbun-ddns", version, about = "Dynamic DNS for Porkbun")]
+struct Cli {
+    /// Configuration file listing the hostnames to update.
+    #[arg(long, short)]
+    config: PathBuf,
+    /// Credentials file, used when the environment variables are not set.
+    #[arg(long)]
+    credentials: Option<PathBuf>,
+    /// Profile of the credentials file.
+    #[arg(long, default_value = "default")]
+    profile: String,
+    /// Seconds between updates, overriding the configuration.
+    #[arg(long)]
+    interval: Option<u64>,
+    /// Update once and exit.
+    #[arg(long)]
+    once: bool,
+    /// API root, for testing against something else than Porkbun.
+    #[arg(long, hide = true, env = "PORKBUN_API_URL")]
+    api_url: Option<Url>,
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let config = Config::from_file(&cli.config)?;
+    let mut updater = Updater::from_config(&config)?;
+
+    let mut provider = ChainProvider::new().with(EnvProvider);
+    if let Some(path) = cli.credentials.or_else(FileProvider::default_path) {
+        provider = provider.with(FileProvider::new(path).profile(cli.profile));
+    }
+    let mut client = Porkbun::new(provider.credentials()?)?;
+    if let Some(url) = cli.api_url {
+        client = client.with_url(url);
+    }
+
+    if cli.once {
+        for update in updater.update(&client)? {
+            let action = match update.action {
+                Action::Unchanged => "unchanged".into(),
+                Action::Created { id } => format!("created {}", id),
+                Action::Edited { id } => format!("edited {}", id),
+            };
+            println!("{} {} {}", update.hostname.fqdn(), update.address, action);
+        }
+        return Ok(());
+    }
+
+    let (shutdown, receiver) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = shutdown.send(());
+    })?;
+
+    let interval = cli
+        .interval
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| config.interval());
+    updater.run(&client, interval, &receiver);
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("porkbun-ddns: {}", err);
+        process::exit(1);
+    }
+}
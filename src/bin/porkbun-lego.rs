@@ -0,0 +1,37 @@
+//! A program for lego's `exec` DNS provider, in its default mode:
+//!
+//! ```sh
+//! porkbun-lego present _acme-challenge.www.example.com. <value>
+//! porkbun-lego cleanup _acme-challenge.www.example.com. <value>
+//! ```
+//!
+//! Lego waits for the record to propagate itself.
+
+mod hook;
+
+use std::{env, error::Error};
+
+const USAGE: &str = "usage: porkbun-lego present|cleanup <fqdn> <value>";
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["present", fqdn, value] => {
+            hook::solver()?.present_fqdn(fqdn, value)?;
+        }
+        ["cleanup", fqdn, value] => {
+            hook::solver()?.cleanup_fqdn(fqdn, value)?;
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn main() {
+    hook::exit("porkbun-lego", run());
+}
@@ -0,0 +1,354 @@
+//! Manage Porkbun DNS records from the command line.
+//!
+//! Credentials are read from `PORKBUN_API_KEY` and `PORKBUN_SECRET_API_KEY`, falling back to a
+//! profile of `$XDG_CONFIG_HOME/porkbun/credentials.toml` (or the file given with `--config`).
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use porkbun_rs::{
+    api::{ApiError, Query},
+    auth::{AuthError, ChainProvider, CredentialProvider, EnvProvider, FileProvider},
+    endpoints::{CreateDns, DeleteDns, DnsContent, DnsContentError, EditDns, Ping, RetrieveDns},
+    CreateResponse, DeleteResponse, EditResponse, PingResponse, Porkbun, PorkbunError, Record,
+    RestError, RetrieveResponse,
+};
+use serde_json::json;
+use std::{path::PathBuf, process};
+use thiserror::Error;
+use url::Url;
+
+/// Exit code for errors not covered by a more specific one.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code when the credentials are missing or rejected by Porkbun.
+const EXIT_AUTH: i32 = 3;
+/// Exit code when the domain or the record does not exist.
+const EXIT_NOT_FOUND: i32 = 4;
+/// Exit code for other errors reported by Porkbun.
+const EXIT_API: i32 = 5;
+
+#[derive(Debug, Parser)]
+#[command(name = "porkbun", version, about = "Manage Porkbun DNS records")]
+struct Cli {
+    /// Credentials file, used when the environment variables are not set.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Profile of the credentials file.
+    #[arg(long, global = true, default_value = "default")]
+    profile: String,
+    /// Output format.
+    #[arg(long, short, global = true, value_enum, default_value_t = Output::Table)]
+    output: Output,
+    /// API root, for testing against something else than Porkbun.
+    #[arg(long, global = true, hide = true, env = "PORKBUN_API_URL")]
+    api_url: Option<Url>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check the credentials and show the address Porkbun sees.
+    Ping,
+    /// Manage DNS records.
+    #[command(subcommand)]
+    Dns(Dns),
+}
+
+#[derive(Debug, Subcommand)]
+enum Dns {
+    /// List the records of a domain.
+    List { domain: String },
+    /// Create a record.
+    Create {
+        domain: String,
+        #[command(flatten)]
+        record: RecordArgs,
+    },
+    /// Replace a record.
+    Edit {
+        domain: String,
+        id: String,
+        #[command(flatten)]
+        record: RecordArgs,
+    },
+    /// Delete a record.
+    Delete { domain: String, id: String },
+}
+
+#[derive(Debug, Args)]
+struct RecordArgs {
+    /// Record type, such as A, CNAME or TXT.
+    #[arg(long = "type", short = 't')]
+    record_type: String,
+    /// Record content.
+    #[arg(long, short)]
+    content: String,
+    /// Subdomain, without the domain; the domain itself if omitted.
+    #[arg(long, short)]
+    name: Option<String>,
+    /// Time to live, in seconds.
+    #[arg(long)]
+    ttl: Option<u32>,
+    /// Priority, for MX and SRV records.
+    #[arg(long)]
+    prio: Option<u16>,
+}
+
+impl RecordArgs {
+    fn content(&self) -> Result<DnsContent, DnsContentError> {
+        DnsContent::new(&self.record_type, &self.content, self.prio)
+    }
+}
+
+#[derive(Debug, Error)]
+enum CliError {
+    #[error("{}", source)]
+    Auth {
+        #[from]
+        source: AuthError,
+    },
+    #[error("{}", source)]
+    Client {
+        #[from]
+        source: PorkbunError,
+    },
+    #[error("{}", source)]
+    Record {
+        #[from]
+        source: DnsContentError,
+    },
+    #[error("{}", source)]
+    Api {
+        #[from]
+        source: ApiError<RestError>,
+    },
+    #[error("failed to write JSON: {}", source)]
+    Json {
+        #[from]
+        source: serde_json::Error,
+    },
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Auth { .. } => EXIT_AUTH,
+            CliError::Api {
+                source: ApiError::PorkBun { message, .. },
+            } => porkbun_exit_code(message),
+            CliError::Api {
+                source: ApiError::PorkBunUnrecognized { .. },
+            } => EXIT_API,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+/// Classify an error message from Porkbun, which only reports errors as text.
+fn porkbun_exit_code(message: &str) -> i32 {
+    let message = message.to_ascii_lowercase();
+    if message.contains("api key") || message.contains("api access") {
+        EXIT_AUTH
+    } else if message.contains("invalid domain")
+        || message.contains("record id")
+        || message.contains("not found")
+    {
+        EXIT_NOT_FOUND
+    } else {
+        EXIT_API
+    }
+}
+
+fn client(cli: &Cli) -> Result<Porkbun, CliError> {
+    let mut provider = ChainProvider::new().with(EnvProvider);
+    if let Some(path) = cli.config.clone().or_else(FileProvider::default_path) {
+        provider = provider.with(FileProvider::new(path).profile(cli.profile.as_str()));
+    }
+
+    let client = Porkbun::new(provider.credentials()?)?;
+    Ok(match &cli.api_url {
+        Some(url) => client.with_url(url.clone()),
+        None => client,
+    })
+}
+
+/// Lay out rows under headers, in columns as wide as their widest cell.
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("{}\n", line.trim_end())
+    };
+
+    let mut out = line(headers.to_vec());
+    for row in rows {
+        out.push_str(&line(row.iter().map(String::as_str).collect()));
+    }
+    out
+}
+
+fn records_table(records: &[Record]) -> String {
+    let rows = records
+        .iter()
+        .map(|record| {
+            vec![
+                record.id.clone(),
+                record.name.clone(),
+                record.record_type.clone(),
+                record.content.clone(),
+                record.ttl.clone(),
+                record.priority.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    table(&["ID", "NAME", "TYPE", "CONTENT", "TTL", "PRIO"], &rows)
+}
+
+fn run(cli: &Cli) -> Result<String, CliError> {
+    let client = client(cli)?;
+    let json = cli.output == Output::Json;
+
+    Ok(match &cli.command {
+        Command::Ping => {
+            let rsp: PingResponse = Ping::builder().build().unwrap().query(&client)?;
+            if json {
+                serde_json::to_string_pretty(&json!({ "yourIp": rsp.your_ip }))?
+            } else {
+                rsp.your_ip
+            }
+        }
+        Command::Dns(Dns::List { domain }) => {
+            let rsp: RetrieveResponse = RetrieveDns::builder()
+                .domain(domain.as_str())
+                .build()
+                .map_err(ApiError::endpoint)?
+                .query(&client)?;
+            if json {
+                serde_json::to_string_pretty(&rsp.records)?
+            } else {
+                records_table(&rsp.records)
+            }
+        }
+        Command::Dns(Dns::Create { domain, record }) => {
+            let mut endpoint = CreateDns::builder();
+            endpoint.domain(domain.as_str()).record(record.content()?);
+            if let Some(name) = &record.name {
+                endpoint.name(name.as_str());
+            }
+            if let Some(ttl) = record.ttl {
+                endpoint.ttl(ttl);
+            }
+            let rsp: CreateResponse = endpoint
+                .build()
+                .map_err(ApiError::endpoint)?
+                .query(&client)?;
+            if json {
+                serde_json::to_string_pretty(&json!({ "id": rsp.id }))?
+            } else {
+                rsp.id
+            }
+        }
+        Command::Dns(Dns::Edit { domain, id, record }) => {
+            let mut endpoint = EditDns::builder();
+            endpoint
+                .domain(domain.as_str())
+                .id(id.as_str())
+                .record(record.content()?);
+            if let Some(name) = &record.name {
+                endpoint.name(name.as_str());
+            }
+            if let Some(ttl) = record.ttl {
+                endpoint.ttl(ttl);
+            }
+            let _: EditResponse = endpoint
+                .build()
+                .map_err(ApiError::endpoint)?
+                .query(&client)?;
+            if json {
+                serde_json::to_string_pretty(&json!({ "id": id }))?
+            } else {
+                format!("edited {}", id)
+            }
+        }
+        Command::Dns(Dns::Delete { domain, id }) => {
+            let _: DeleteResponse = DeleteDns::builder()
+                .domain(domain.as_str())
+                .id(id.as_str())
+                .build()
+                .map_err(ApiError::endpoint)?
+                .query(&client)?;
+            if json {
+                serde_json::to_string_pretty(&json!({ "id": id }))?
+            } else {
+                format!("deleted {}", id)
+            }
+        }
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(out) => println!("{}", out.trim_end()),
+        Err(err) => {
+            eprintln!("porkbun: {}", err);
+            process::exit(err.exit_code());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::{porkbun_exit_code, table, Cli, EXIT_API, EXIT_AUTH, EXIT_NOT_FOUND};
+
+    #[test]
+    fn cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(porkbun_exit_code("Invalid API key. (002)"), EXIT_AUTH);
+        assert_eq!(porkbun_exit_code("Invalid domain."), EXIT_NOT_FOUND);
+        assert_eq!(
+            porkbun_exit_code("Delete error: Invalid record id."),
+            EXIT_NOT_FOUND,
+        );
+        assert_eq!(
+            porkbun_exit_code("Create error: Duplicate record."),
+            EXIT_API,
+        );
+    }
+
+    #[test]
+    fn table_layout() {
+        let rows = vec![
+            vec!["1".into(), "www.example.com".into(), "".into()],
+            vec!["22".into(), "example.com".into(), "10".into()],
+        ];
+        assert_eq!(
+            table(&["ID", "NAME", "PRIO"], &rows),
+            "ID  NAME             PRIO\n\
+             1   www.example.com\n\
+             22  example.com      10\n",
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! Cache the records of domains between queries.
+//!
+//! A [`CachingClient`] wraps another client and keeps the responses of `dns/retrieve` for a
+//! while, so that code retrieving the same domain again and again only costs one API call.
+//! Creating, editing or deleting a record of a domain through the client drops what it kept
+//! for that domain; changes made by other clients are only seen once the entries expire.
+
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{request::Builder as RequestBuilder, HeaderMap, Response, StatusCode, Version};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use url::Url;
+
+#[cfg(feature = "async")]
+use crate::api::AsyncClient;
+#[cfg(feature = "sync")]
+use crate::api::Client;
+use crate::{api::ApiError, auth::Auth};
+
+/// A response kept by the cache.
+#[derive(Debug)]
+struct Entry {
+    domain: String,
+    stored: Instant,
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Entry {
+    fn response(&self) -> Response<Bytes> {
+        let mut rsp = Response::new(self.body.clone());
+        *rsp.status_mut() = self.status;
+        *rsp.version_mut() = self.version;
+        *rsp.headers_mut() = self.headers.clone();
+        rsp
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<String, Entry>,
+    /// Bumped on every invalidation, so that retrievals racing with a change are not kept.
+    generation: u64,
+}
+
+/// What a request does to the records of a domain.
+#[derive(Debug, PartialEq, Eq)]
+enum Access {
+    /// It retrieves records; the response may be cached under `key`.
+    Retrieve { key: String, domain: String },
+    /// It changes records.
+    Write { domain: String },
+    /// It does not touch records.
+    Other,
+}
+
+fn normalize(domain: &str) -> String {
+    domain.trim_end_matches('.').to_lowercase()
+}
+
+/// Classify a request given its endpoint, relative to the API root.
+fn access(endpoint: &str) -> Access {
+    let endpoint = endpoint.trim_matches('/');
+    let mut segments = endpoint.split('/');
+    match (segments.next(), segments.next(), segments.next()) {
+        (Some("dns"), Some(operation), Some(domain)) => {
+            let domain = normalize(domain);
+            if operation.starts_with("retrieve") {
+                Access::Retrieve {
+                    key: endpoint.to_lowercase(),
+                    domain,
+                }
+            } else {
+                Access::Write { domain }
+            }
+        }
+        _ => Access::Other,
+    }
+}
+
+/// A client which caches the records it retrieves.
+///
+/// Only successful responses are kept, each for the duration given to
+/// [`CachingClient::new`].
+#[derive(Debug)]
+pub struct CachingClient<C> {
+    client: C,
+    ttl: Duration,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<C> CachingClient<C> {
+    /// Cache the records retrieved through `client` for `ttl`.
+    pub fn new(client: C, ttl: Duration) -> Self {
+        Self {
+            client,
+            ttl,
+            entries: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    /// Unwrap the client, dropping the cache.
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+
+    /// How many retrievals were answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// How many retrievals were sent to the wrapped client.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Drop what is kept for `domain`, for instance after changing it through another client.
+    pub fn invalidate(&self, domain: &str) {
+        let domain = normalize(domain);
+        let mut entries = self.entries.lock().unwrap();
+        entries.entries.retain(|_, entry| entry.domain != domain);
+        entries.generation += 1;
+    }
+
+    /// Drop everything kept.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.entries.clear();
+        entries.generation += 1;
+    }
+
+    /// The cached response for `key`, or the generation to store a fresh one with.
+    fn lookup(&self, key: &str) -> Result<Response<Bytes>, u64> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.entries.get(key) {
+            Some(entry) if entry.stored.elapsed() < self.ttl => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(entry.response())
+            }
+            _ => {
+                entries.entries.remove(key);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Err(entries.generation)
+            }
+        }
+    }
+
+    fn store(&self, key: String, domain: String, generation: u64, rsp: &Response<Bytes>) {
+        let mut entries = self.entries.lock().unwrap();
+        if !rsp.status().is_success() || entries.generation != generation {
+            return;
+        }
+        let entry = Entry {
+            domain,
+            stored: Instant::now(),
+            status: rsp.status(),
+            version: rsp.version(),
+            headers: rsp.headers().clone(),
+            body: rsp.body().clone(),
+        };
+        entries.entries.insert(key, entry);
+    }
+}
+
+fn endpoint_access(base: &Url, request: &RequestBuilder) -> Access {
+    let uri = request.uri_ref().unwrap().to_string();
+    access(uri.strip_prefix(base.as_str()).unwrap_or(&uri))
+}
+
+#[cfg(feature = "sync")]
+impl<C> Client for CachingClient<C>
+where
+    C: Client,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        self.client.rest_endpoint(endpoint)
+    }
+
+    fn rest(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        let base = self.client.rest_endpoint("")?;
+        match endpoint_access(&base, &request) {
+            Access::Retrieve { key, domain } => {
+                let generation = match self.lookup(&key) {
+                    Ok(rsp) => return Ok(rsp),
+                    Err(generation) => generation,
+                };
+                let rsp = self.client.rest(request, body)?;
+                self.store(key, domain, generation, &rsp);
+                Ok(rsp)
+            }
+            Access::Write { domain } => {
+                let rsp = self.client.rest(request, body);
+                self.invalidate(&domain);
+                rsp
+            }
+            Access::Other => self.client.rest(request, body),
+        }
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        self.client.auth()
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<C> AsyncClient for CachingClient<C>
+where
+    C: AsyncClient + Sync,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        self.client.rest_endpoint(endpoint)
+    }
+
+    async fn rest_async(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        let base = self.client.rest_endpoint("")?;
+        match endpoint_access(&base, &request) {
+            Access::Retrieve { key, domain } => {
+                let generation = match self.lookup(&key) {
+                    Ok(rsp) => return Ok(rsp),
+                    Err(generation) => generation,
+                };
+                let rsp = self.client.rest_async(request, body).await?;
+                self.store(key, domain, generation, &rsp);
+                Ok(rsp)
+            }
+            Access::Write { domain } => {
+                let rsp = self.client.rest_async(request, body).await;
+                self.invalidate(&domain);
+                rsp
+            }
+            Access::Other => self.client.rest_async(request, body).await,
+        }
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        self.client.auth()
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        api::{AsyncQuery, Query},
+        endpoints::{CreateDns, DnsContent, Ping, RetrieveDns},
+        test::FakePorkbun,
+        CreateResponse, PingResponse, RetrieveResponse,
+    };
+
+    use super::{access, Access, CachingClient};
+
+    fn retrieve(domain: &str) -> RetrieveDns<'_> {
+        RetrieveDns::builder().domain(domain).build().unwrap()
+    }
+
+    fn create(content: &str) -> CreateDns<'static> {
+        CreateDns::builder()
+            .domain("example.com")
+            .record(DnsContent::A {
+                content: content.parse().unwrap(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn classify() {
+        assert_eq!(
+            access("dns/retrieve/Example.com"),
+            Access::Retrieve {
+                key: "dns/retrieve/example.com".into(),
+                domain: "example.com".into(),
+            }
+        );
+        assert_eq!(
+            access("dns/delete/example.com./1"),
+            Access::Write {
+                domain: "example.com".into(),
+            }
+        );
+        assert_eq!(access("domain/listAll"), Access::Other);
+        assert_eq!(access("ping"), Access::Other);
+    }
+
+    #[test]
+    fn read_through() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_domain("example.net");
+        let client = CachingClient::new(fake, Duration::from_secs(60));
+
+        let rsp: RetrieveResponse = retrieve("example.com").query(&client).unwrap();
+        assert!(rsp.records.is_empty());
+        let _: RetrieveResponse = retrieve("example.net").query(&client).unwrap();
+        let _: RetrieveResponse = retrieve("example.com").query(&client).unwrap();
+        let _: PingResponse = Ping::builder().build().unwrap().query(&client).unwrap();
+        assert_eq!((client.hits(), client.misses()), (1, 2));
+
+        let _: CreateResponse = create("192.0.2.1").query(&client).unwrap();
+        let rsp: RetrieveResponse = retrieve("example.com").query(&client).unwrap();
+        assert_eq!(rsp.records.len(), 1);
+        let _: RetrieveResponse = retrieve("example.net").query(&client).unwrap();
+        assert_eq!((client.hits(), client.misses()), (2, 3));
+
+        // Failed writes may have changed something as well.
+        let err: Result<CreateResponse, _> = create("192.0.2.1").query(&client);
+        assert!(err.is_err());
+        let _: RetrieveResponse = retrieve("example.com").query(&client).unwrap();
+        assert_eq!((client.hits(), client.misses()), (2, 4));
+
+        // Errors are not kept.
+        for _ in 0..2 {
+            let err: Result<RetrieveResponse, _> = retrieve("example.org").query(&client);
+            assert!(err.is_err());
+        }
+        assert_eq!((client.hits(), client.misses()), (2, 6));
+    }
+
+    #[test]
+    fn expiry() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let client = CachingClient::new(fake, Duration::ZERO);
+
+        let _: RetrieveResponse = retrieve("example.com").query(&client).unwrap();
+        let _: RetrieveResponse = retrieve("example.com").query(&client).unwrap();
+        assert_eq!((client.hits(), client.misses()), (0, 2));
+    }
+
+    #[tokio::test]
+    async fn read_through_async() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let client = CachingClient::new(fake, Duration::from_secs(60));
+
+        let _: RetrieveResponse = retrieve("example.com").query_async(&client).await.unwrap();
+        let _: RetrieveResponse = retrieve("example.com").query_async(&client).await.unwrap();
+        assert_eq!((client.hits(), client.misses()), (1, 1));
+
+        // Changes made behind the cache's back are only seen once invalidated.
+        let _: CreateResponse = create("192.0.2.1").query(client.inner()).unwrap();
+        let rsp: RetrieveResponse = retrieve("example.com").query_async(&client).await.unwrap();
+        assert!(rsp.records.is_empty());
+        client.invalidate("EXAMPLE.com");
+        let rsp: RetrieveResponse = retrieve("example.com").query_async(&client).await.unwrap();
+        assert_eq!(rsp.records.len(), 1);
+        assert_eq!((client.hits(), client.misses()), (2, 2));
+    }
+}
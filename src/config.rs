@@ -0,0 +1,588 @@
+//! Describe the records of domains in YAML or TOML files.
+//!
+//! ```yaml
+//! include:
+//!   - mail.yaml           # relative to this file
+//!
+//! templates:
+//!   web:
+//!     - type: A
+//!       content: 192.0.2.1
+//!     - name: www
+//!       type: CNAME
+//!       content: "@"      # the domain itself
+//!
+//! domains:
+//!   example.com:
+//!     ttl: 3600           # for records without one
+//!     templates: [web, google-mail]
+//!     records:
+//!       - name: _dmarc
+//!         type: TXT
+//!         content: v=DMARC1; p=reject
+//!       - type: MX
+//!         content: mx.example.net
+//!         priority: 10
+//! ```
+//!
+//! TOML files have the same structure. Files are read as YAML when their name ends in
+//! `.yaml` or `.yml`, which needs the `yaml` feature, and as TOML otherwise. A file included
+//! more than once, for instance by two files which both include shared templates, is only read
+//! the first time.
+
+use serde::Deserialize;
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    convert::TryFrom,
+    error::Error as StdError,
+    fs,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{
+    endpoints::{CreateDns, CreateDnsBuilderError, DnsContent},
+    sync::{DesiredRecord, DesiredZone},
+};
+
+/// Errors which may occur while loading a configuration.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// A file could not be read.
+    #[error("failed to read {}: {}", path.display(), source)]
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: std::io::Error,
+    },
+    /// A file is not valid.
+    #[error("{}: {}", path.display(), source)]
+    Parse {
+        /// The path of the file.
+        path: PathBuf,
+        /// The line of the error, starting at 1, if known.
+        line: Option<usize>,
+        /// The column of the error, starting at 1, if known.
+        column: Option<usize>,
+        /// The source of the error.
+        source: Box<dyn StdError + Send + Sync>,
+    },
+    /// YAML files need the `yaml` feature.
+    #[error("{}: YAML files need the `yaml` feature", path.display())]
+    YamlUnsupported {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// A file includes itself, directly or not.
+    #[error("{}: includes itself", path.display())]
+    IncludeCycle {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// Two files define the same domain or template.
+    #[error("{}: {} `{}` is already defined", path.display(), kind, name)]
+    Duplicate {
+        /// The path of the second definition.
+        path: PathBuf,
+        /// `domain` or `template`.
+        kind: &'static str,
+        /// The name of the domain or template.
+        name: String,
+    },
+    /// A domain uses a template which is not defined.
+    #[error("{}: domain `{}` uses unknown template `{}`", path.display(), domain, template)]
+    UnknownTemplate {
+        /// The path of the file defining the domain.
+        path: PathBuf,
+        /// The domain.
+        domain: String,
+        /// The template.
+        template: String,
+    },
+}
+
+type ConfigResult<T> = Result<T, ConfigError>;
+
+/// A record as written in a file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRecord {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(rename = "type")]
+    record_type: String,
+    content: String,
+    #[serde(default, alias = "prio")]
+    priority: Option<u16>,
+    #[serde(default)]
+    ttl: Option<u32>,
+}
+
+/// A record whose content may still refer to the domain as `@`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawRecord")]
+struct Record {
+    name: Option<String>,
+    record_type: String,
+    content: String,
+    priority: Option<u16>,
+    ttl: Option<u32>,
+}
+
+impl TryFrom<RawRecord> for Record {
+    type Error = String;
+
+    fn try_from(raw: RawRecord) -> Result<Self, Self::Error> {
+        // Check the record now, so errors point at it. `@` stands for the domain, which is not
+        // known yet but is a valid name anyway.
+        DnsContent::new(&raw.record_type, &raw.content, raw.priority)
+            .map_err(|err| err.to_string())?;
+        Ok(Self {
+            name: raw.name.filter(|name| !name.is_empty() && name != "@"),
+            record_type: raw.record_type,
+            content: raw.content,
+            priority: raw.priority,
+            ttl: raw.ttl,
+        })
+    }
+}
+
+impl Record {
+    fn resolve(&self, domain: &str, default_ttl: Option<u32>) -> DesiredRecord {
+        let content = match self.record_type.to_uppercase().as_str() {
+            "CNAME" | "MX" | "NS" | "SRV" if self.content.trim() == "@" => domain.into(),
+            _ => self.content.clone(),
+        };
+        DesiredRecord {
+            name: self.name.clone(),
+            record: DnsContent::new(&self.record_type, &content, self.priority)
+                .expect("checked when parsed"),
+            ttl: self.ttl.or(default_ttl),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Domain {
+    #[serde(default)]
+    ttl: Option<u32>,
+    #[serde(default)]
+    templates: Vec<String>,
+    #[serde(default)]
+    records: Vec<Record>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    #[serde(default)]
+    include: Vec<PathBuf>,
+    #[serde(default)]
+    templates: BTreeMap<String, Vec<Record>>,
+    #[serde(default)]
+    domains: BTreeMap<String, Domain>,
+}
+
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml") | Some("yml")
+    )
+}
+
+#[cfg(feature = "yaml")]
+fn from_yaml(path: &Path, data: &str) -> ConfigResult<File> {
+    serde_yaml::from_str(data).map_err(|source| {
+        let location = source.location();
+        ConfigError::Parse {
+            path: path.into(),
+            line: location.as_ref().map(|location| location.line()),
+            column: location.as_ref().map(|location| location.column()),
+            source: source.into(),
+        }
+    })
+}
+
+#[cfg(not(feature = "yaml"))]
+fn from_yaml(path: &Path, _: &str) -> ConfigResult<File> {
+    Err(ConfigError::YamlUnsupported { path: path.into() })
+}
+
+fn from_toml(path: &Path, data: &str) -> ConfigResult<File> {
+    toml::from_str(data).map_err(|source| {
+        let location = source.line_col();
+        ConfigError::Parse {
+            path: path.into(),
+            line: location.map(|(line, _)| line + 1),
+            column: location.map(|(_, column)| column + 1),
+            source: source.into(),
+        }
+    })
+}
+
+/// The records of domains, with includes and templates resolved.
+#[derive(Debug, Clone, Default)]
+pub struct ZoneConfig {
+    domains: BTreeMap<String, Vec<DesiredRecord>>,
+}
+
+/// What is read from a file and the files it includes.
+#[derive(Default)]
+struct Loader {
+    stack: Vec<PathBuf>,
+    loaded: BTreeSet<PathBuf>,
+    templates: BTreeMap<String, (PathBuf, Vec<Record>)>,
+    domains: BTreeMap<String, (PathBuf, Domain)>,
+}
+
+impl Loader {
+    fn load(&mut self, path: &Path) -> ConfigResult<()> {
+        let canonical = fs::canonicalize(path).map_err(|source| ConfigError::Io {
+            path: path.into(),
+            source,
+        })?;
+        if self.stack.contains(&canonical) {
+            return Err(ConfigError::IncludeCycle { path: path.into() });
+        }
+        // A file included along several paths is only read once.
+        if !self.loaded.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let data = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.into(),
+            source,
+        })?;
+        let file = if is_yaml(path) {
+            from_yaml(path, &data)?
+        } else {
+            from_toml(path, &data)?
+        };
+
+        self.stack.push(canonical);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for include in &file.include {
+            self.load(&dir.join(include))?;
+        }
+        self.stack.pop();
+
+        for (name, records) in file.templates {
+            match self.templates.entry(name) {
+                Entry::Vacant(entry) => {
+                    entry.insert((path.into(), records));
+                }
+                Entry::Occupied(entry) => {
+                    return Err(ConfigError::Duplicate {
+                        path: path.into(),
+                        kind: "template",
+                        name: entry.key().clone(),
+                    })
+                }
+            }
+        }
+        for (name, domain) in file.domains {
+            match self
+                .domains
+                .entry(name.trim_end_matches('.').to_lowercase())
+            {
+                Entry::Vacant(entry) => {
+                    entry.insert((path.into(), domain));
+                }
+                Entry::Occupied(entry) => {
+                    return Err(ConfigError::Duplicate {
+                        path: path.into(),
+                        kind: "domain",
+                        name: entry.key().clone(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(self) -> ConfigResult<ZoneConfig> {
+        let mut domains = BTreeMap::new();
+        for (name, (path, domain)) in self.domains {
+            let mut records = Vec::new();
+            for template in &domain.templates {
+                let (_, template_records) =
+                    self.templates
+                        .get(template)
+                        .ok_or_else(|| ConfigError::UnknownTemplate {
+                            path: path.clone(),
+                            domain: name.clone(),
+                            template: template.clone(),
+                        })?;
+                records.extend(
+                    template_records
+                        .iter()
+                        .map(|record| record.resolve(&name, domain.ttl)),
+                );
+            }
+            records.extend(
+                domain
+                    .records
+                    .iter()
+                    .map(|record| record.resolve(&name, domain.ttl)),
+            );
+            domains.insert(name, records);
+        }
+        Ok(ZoneConfig { domains })
+    }
+}
+
+impl ZoneConfig {
+    /// Load a file and the files it includes.
+    pub fn load<P>(path: P) -> ConfigResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut loader = Loader::default();
+        loader.load(path.as_ref())?;
+        loader.resolve()
+    }
+
+    /// The domains, in order.
+    pub fn domains(&self) -> impl Iterator<Item = &str> {
+        self.domains.keys().map(String::as_str)
+    }
+
+    /// The records of a domain, templates first.
+    pub fn records(&self, domain: &str) -> Option<&[DesiredRecord]> {
+        self.domains.get(domain).map(Vec::as_slice)
+    }
+
+    /// The endpoints creating the records of a domain, unless a name is not valid.
+    pub fn create_endpoints<'a>(
+        &'a self,
+        domain: &'a str,
+    ) -> Result<Vec<CreateDns<'a>>, CreateDnsBuilderError> {
+        self.records(domain)
+            .unwrap_or_default()
+            .iter()
+            .map(|record| record.create(domain))
+            .collect()
+    }
+
+    /// The desired state of a domain, to plan the changes bringing it in line, including the
+    /// [`EditDns`](crate::endpoints::EditDns) ones.
+    pub fn zone(&self, domain: &str) -> Option<DesiredZone> {
+        self.records(domain)
+            .map(|records| DesiredZone::new(domain, records.iter().cloned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use crate::{endpoints::DnsContent, sync::DesiredRecord};
+
+    use super::{ConfigError, ZoneConfig};
+
+    /// A fresh directory for the files of a test.
+    fn dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("porkbun-config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn toml() {
+        let dir = dir("toml");
+        fs::write(
+            dir.join("mail.toml"),
+            r#"
+            [[templates.mail]]
+            type = "MX"
+            content = "mx.example.net"
+            priority = 10
+
+            [[templates.mail]]
+            type = "TXT"
+            content = "v=spf1 mx -all"
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("zones.toml"),
+            r#"
+            include = ["mail.toml"]
+
+            [domains."Example.com"]
+            ttl = 3600
+            templates = ["mail"]
+
+            [[domains."Example.com".records]]
+            name = "www"
+            type = "CNAME"
+            content = "@"
+            ttl = 600
+            "#,
+        )
+        .unwrap();
+
+        let config = ZoneConfig::load(dir.join("zones.toml")).unwrap();
+        assert_eq!(config.domains().collect::<Vec<_>>(), ["example.com"]);
+        assert_eq!(
+            config.records("example.com").unwrap(),
+            [
+                DesiredRecord::new(DnsContent::Mx {
+                    content: "mx.example.net".into(),
+                    priority: 10,
+                })
+                .ttl(3600),
+                DesiredRecord::new(DnsContent::Txt {
+                    content: "v=spf1 mx -all".into(),
+                })
+                .ttl(3600),
+                DesiredRecord::new(DnsContent::Cname {
+                    content: "example.com".into(),
+                })
+                .name("www")
+                .ttl(600),
+            ],
+        );
+        assert_eq!(config.create_endpoints("example.com").unwrap().len(), 3);
+        assert!(config.zone("example.com").is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shared_include() {
+        let dir = dir("shared");
+        fs::write(
+            dir.join("shared-templates.toml"),
+            "[[templates.web]]\ntype = \"A\"\ncontent = \"192.0.2.1\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b.toml"),
+            "include = [\"shared-templates.toml\"]\n[domains.\"example.com\"]\ntemplates = [\"web\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("c.toml"),
+            "include = [\"./shared-templates.toml\"]\n[domains.\"example.net\"]\ntemplates = [\"web\"]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("a.toml"), "include = [\"b.toml\", \"c.toml\"]\n").unwrap();
+
+        let config = ZoneConfig::load(dir.join("a.toml")).unwrap();
+        assert_eq!(
+            config.domains().collect::<Vec<_>>(),
+            ["example.com", "example.net"],
+        );
+        for domain in ["example.com", "example.net"] {
+            assert_eq!(
+                config.records(domain).unwrap(),
+                [DesiredRecord::new(DnsContent::A {
+                    content: "192.0.2.1".parse().unwrap(),
+                })],
+            );
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn toml_error_location() {
+        let dir = dir("location");
+        let path = dir.join("zones.toml");
+        fs::write(
+            &path,
+            "[[domains.\"example.com\".records]]\ntype = \"A\"\ncontent = \"2001:db8::1\"\n",
+        )
+        .unwrap();
+
+        let err = ZoneConfig::load(&path).unwrap_err();
+        if let ConfigError::Parse { line, .. } = &err {
+            assert!(line.is_some());
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+        let message = err.to_string();
+        assert!(message.starts_with(&path.display().to_string()));
+        assert!(message.contains("invalid A record content `2001:db8::1`"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let dir = dir("errors");
+        fs::write(dir.join("a.toml"), "include = [\"b.toml\"]\n").unwrap();
+        fs::write(dir.join("b.toml"), "include = [\"a.toml\"]\n").unwrap();
+        assert!(matches!(
+            ZoneConfig::load(dir.join("a.toml")).unwrap_err(),
+            ConfigError::IncludeCycle { .. },
+        ));
+
+        fs::write(
+            dir.join("c.toml"),
+            "[domains.\"example.com\"]\ntemplates = [\"web\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            ZoneConfig::load(dir.join("c.toml"))
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "{}: domain `example.com` uses unknown template `web`",
+                dir.join("c.toml").display(),
+            ),
+        );
+
+        fs::write(
+            dir.join("d.toml"),
+            "include = [\"c.toml\"]\n[domains.\"example.com\"]\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            ZoneConfig::load(dir.join("d.toml")).unwrap_err(),
+            ConfigError::Duplicate { kind: "domain", .. },
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        let dir = dir("yaml");
+        let path = dir.join("zones.yaml");
+        fs::write(
+            &path,
+            "domains:\n  example.com:\n    records:\n      - type: A\n        content: 192.0.2.1\n      - type: MX\n        content: mx.example.net\n",
+        )
+        .unwrap();
+
+        let err = ZoneConfig::load(&path).unwrap_err();
+        assert!(err.to_string().contains("MX records need a priority"));
+        if let ConfigError::Parse { line, .. } = err {
+            assert!(line.is_some());
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+
+        fs::write(
+            &path,
+            "domains:\n  example.com:\n    records:\n      - type: A\n        content: 192.0.2.1\n",
+        )
+        .unwrap();
+        let config = ZoneConfig::load(&path).unwrap();
+        assert_eq!(
+            config.records("example.com").unwrap(),
+            [DesiredRecord::new(DnsContent::A {
+                content: "192.0.2.1".parse().unwrap(),
+            })],
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
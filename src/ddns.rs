@@ -0,0 +1,568 @@
+//! Keep `A` and `AAAA` records pointing at the public address of this host.
+//!
+//! The address is the one Porkbun's `ping` endpoint reports. Records are only edited or
+//! created when it differs from the last address written, which is kept in an optional state
+//! file so restarts do not cost any API call either.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::{
+    api::{ApiError, Client, Query},
+    endpoints::{CreateDns, DnsContent, EditDns, Ping, RetrieveDns},
+    CreateResponse, EditResponse, PingResponse, Record, RetrieveResponse,
+};
+
+/// Errors which may occur while reading or writing the configuration or the state.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum FileError {
+    /// The file could not be read or written.
+    #[error("failed to access {}: {}", path.display(), source)]
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: io::Error,
+    },
+    /// The configuration file is not valid.
+    #[error("invalid configuration file {}: {}", path.display(), source)]
+    Toml {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: toml::de::Error,
+    },
+    /// The state file is not valid.
+    #[error("invalid state file {}: {}", path.display(), source)]
+    Json {
+        /// The path of the file.
+        path: PathBuf,
+        /// The source of the error.
+        source: serde_json::Error,
+    },
+}
+
+/// Errors which may occur while updating records.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DdnsError<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// A request to Porkbun failed.
+    #[error("{}", source)]
+    Api {
+        /// The source of the error.
+        #[from]
+        source: ApiError<E>,
+    },
+    /// Porkbun reported something else than an address.
+    #[error("porkbun reported an invalid address: `{}`", address)]
+    Address {
+        /// What was reported.
+        address: String,
+    },
+    /// The state could not be saved.
+    #[error("{}", source)]
+    File {
+        /// The source of the error.
+        #[from]
+        source: FileError,
+    },
+}
+
+type DdnsResult<T, E> = Result<T, DdnsError<E>>;
+
+/// A hostname to keep up to date.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hostname {
+    /// The domain, as registered with Porkbun.
+    pub domain: String,
+    /// The subdomain, without the domain; the domain itself if `None`.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl Hostname {
+    /// A hostname in `domain`, or the domain itself if `name` is `None`.
+    pub fn new<D, N>(domain: D, name: Option<N>) -> Self
+    where
+        D: Into<String>,
+        N: Into<String>,
+    {
+        Self {
+            domain: domain.into().to_lowercase(),
+            name: name.map(|name| name.into().to_lowercase()),
+        }
+    }
+
+    /// The fully qualified name, as Porkbun lists it.
+    pub fn fqdn(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}.{}", name, self.domain),
+            None => self.domain.clone(),
+        }
+    }
+}
+
+/// What was done for a hostname.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// The record already had the address.
+    Unchanged,
+    /// A record was created.
+    Created {
+        /// The id of the new record.
+        id: String,
+    },
+    /// A record was edited.
+    Edited {
+        /// The id of the record.
+        id: String,
+    },
+}
+
+/// The outcome of an update for a hostname.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    /// The hostname.
+    pub hostname: Hostname,
+    /// The address of the record.
+    pub address: IpAddr,
+    /// What was done.
+    pub action: Action,
+}
+
+/// The configuration of an updater, as read from a TOML file.
+///
+/// ```toml
+/// interval = 300
+/// state = "/var/lib/porkbun-ddns/state.json"
+///
+/// [[hosts]]
+/// domain = "example.com"
+/// name = "home"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// Seconds between updates.
+    #[serde(default = "Config::default_interval")]
+    pub interval: u64,
+    /// Where to keep the last addresses written.
+    #[serde(default)]
+    pub state: Option<PathBuf>,
+    /// The TTL of the records, in seconds.
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    /// The hostnames to keep up to date.
+    pub hosts: Vec<Hostname>,
+}
+
+impl Config {
+    fn default_interval() -> u64 {
+        300
+    }
+
+    /// Read a configuration file.
+    pub fn from_file<P>(path: P) -> Result<Self, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| FileError::Io {
+            path: path.into(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|source| FileError::Toml {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// The time between updates.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval)
+    }
+}
+
+/// The last address written for each hostname and record type.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    addresses: BTreeMap<String, IpAddr>,
+}
+
+impl State {
+    fn key(hostname: &Hostname, address: &IpAddr) -> String {
+        format!("{}/{}", hostname.fqdn(), record_type(address))
+    }
+}
+
+fn record_type(address: &IpAddr) -> &'static str {
+    match address {
+        IpAddr::V4(_) => "A",
+        IpAddr::V6(_) => "AAAA",
+    }
+}
+
+/// Points hostnames at the public address of this host.
+#[derive(Debug)]
+pub struct Updater {
+    hosts: Vec<Hostname>,
+    ttl: Option<u32>,
+    state_path: Option<PathBuf>,
+    state: State,
+}
+
+impl Updater {
+    /// Keep `hosts` up to date, without remembering anything across restarts.
+    pub fn new(hosts: Vec<Hostname>) -> Self {
+        Self {
+            hosts,
+            ttl: None,
+            state_path: None,
+            state: State::default(),
+        }
+    }
+
+    /// An updater for a configuration, loading its state file if there is one.
+    pub fn from_config(config: &Config) -> Result<Self, FileError> {
+        let mut updater = Self::new(config.hosts.clone());
+        updater.ttl = config.ttl;
+        match &config.state {
+            Some(path) => updater.with_state_file(path),
+            None => Ok(updater),
+        }
+    }
+
+    /// Set the TTL of the records which are created or edited.
+    pub fn with_ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Remember the addresses written in `path`, loading it if it exists.
+    pub fn with_state_file<P>(mut self, path: P) -> Result<Self, FileError>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        self.state = match fs::read(&path) {
+            Ok(contents) => {
+                serde_json::from_slice(&contents).map_err(|source| FileError::Json {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => State::default(),
+            Err(source) => return Err(FileError::Io { path, source }),
+        };
+        self.state_path = Some(path);
+        Ok(self)
+    }
+
+    /// Point every hostname at the current public address.
+    ///
+    /// Hostnames whose last written address is the current one are skipped without any API
+    /// call. Others get their first record of the right type edited, or a new one if there is
+    /// none.
+    pub fn update<C>(&mut self, client: &C) -> DdnsResult<Vec<Update>, C::Error>
+    where
+        C: Client,
+    {
+        let ping: PingResponse = Ping::builder().build().unwrap().query(client)?;
+        let address = ping
+            .your_ip
+            .parse::<IpAddr>()
+            .map_err(|_| DdnsError::Address {
+                address: ping.your_ip.clone(),
+            })?;
+
+        let mut records = HashMap::new();
+        let mut updates = Vec::with_capacity(self.hosts.len());
+        for hostname in &self.hosts {
+            let key = State::key(hostname, &address);
+            if self.state.addresses.get(&key) == Some(&address) {
+                updates.push(Update {
+                    hostname: hostname.clone(),
+                    address,
+                    action: Action::Unchanged,
+                });
+                continue;
+            }
+
+            if !records.contains_key(&hostname.domain) {
+                let rsp: RetrieveResponse = RetrieveDns::builder()
+                    .domain(hostname.domain.as_str())
+                    .build()
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+                records.insert(hostname.domain.clone(), rsp.records);
+            }
+
+            let action = self.point(client, hostname, &address, &records[&hostname.domain])?;
+            match &action {
+                Action::Unchanged => {}
+                Action::Created { id } | Action::Edited { id } => {
+                    info!(target: "porkbun", "pointed {} at {} (record {})", hostname.fqdn(), address, id);
+                }
+            }
+
+            self.state.addresses.insert(key, address);
+            self.save()?;
+            updates.push(Update {
+                hostname: hostname.clone(),
+                address,
+                action,
+            });
+        }
+
+        Ok(updates)
+    }
+
+    fn point<C>(
+        &self,
+        client: &C,
+        hostname: &Hostname,
+        address: &IpAddr,
+        records: &[Record],
+    ) -> DdnsResult<Action, C::Error>
+    where
+        C: Client,
+    {
+        let fqdn = hostname.fqdn();
+        let record_type = record_type(address);
+        let existing = records.iter().find(|record| {
+            record.name.eq_ignore_ascii_case(&fqdn) && record.record_type == record_type
+        });
+
+        let content = match address {
+            IpAddr::V4(address) => DnsContent::A { content: *address },
+            IpAddr::V6(address) => DnsContent::Aaaa { content: *address },
+        };
+
+        match existing {
+            Some(record) if record.content.parse::<IpAddr>().ok() == Some(*address) => {
+                Ok(Action::Unchanged)
+            }
+            Some(record) => {
+                let mut endpoint = EditDns::builder();
+                endpoint
+                    .domain(hostname.domain.as_str())
+                    .id(record.id.as_str())
+                    .record(content);
+                if let Some(name) = &hostname.name {
+                    endpoint.name(name.as_str());
+                }
+                if let Some(ttl) = self.ttl {
+                    endpoint.ttl(ttl);
+                }
+                let _: EditResponse = endpoint
+                    .build()
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+                Ok(Action::Edited {
+                    id: record.id.clone(),
+                })
+            }
+            None => {
+                let mut endpoint = CreateDns::builder();
+                endpoint.domain(hostname.domain.as_str()).record(content);
+                if let Some(name) = &hostname.name {
+                    endpoint.name(name.as_str());
+                }
+                if let Some(ttl) = self.ttl {
+                    endpoint.ttl(ttl);
+                }
+                let rsp: CreateResponse = endpoint
+                    .build()
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+                Ok(Action::Created { id: rsp.id })
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), FileError> {
+        let path = match &self.state_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        // Write a sibling file first so an interrupted write never leaves a truncated state.
+        let tmp = path.with_extension("tmp");
+        let contents =
+            serde_json::to_vec_pretty(&self.state).map_err(|source| FileError::Json {
+                path: path.clone(),
+                source,
+            })?;
+        fs::write(&tmp, contents)
+            .and_then(|()| fs::rename(&tmp, path))
+            .map_err(|source| FileError::Io {
+                path: path.clone(),
+                source,
+            })
+    }
+
+    /// Update every `interval` until something is sent on `shutdown` or its sender is dropped.
+    ///
+    /// Failed updates are logged and retried at the next interval.
+    pub fn run<C>(&mut self, client: &C, interval: Duration, shutdown: &Receiver<()>)
+    where
+        C: Client,
+    {
+        loop {
+            if let Err(err) = self.update(client) {
+                warn!(target: "porkbun", "failed to update records: {}", err);
+            }
+
+            match shutdown.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use http::Method;
+    use serde_json::json;
+    use std::{env, fs, sync::mpsc, time::Duration};
+
+    use crate::test::{ExpectedUrl, FakePorkbun, TestClient};
+
+    use super::{Action, Config, DdnsError, Hostname, Updater};
+
+    fn home() -> Hostname {
+        Hostname::new("example.com", Some("home"))
+    }
+
+    #[test]
+    fn creates_then_edits() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_your_ip("192.0.2.1");
+        let mut updater = Updater::new(vec![home(), Hostname::new("example.com", None::<String>)]);
+
+        let updates = updater.update(&fake).unwrap();
+        assert!(matches!(updates[0].action, Action::Created { .. }));
+        assert!(matches!(updates[1].action, Action::Created { .. }));
+
+        let updates = updater.update(&fake).unwrap();
+        assert_eq!(updates[0].action, Action::Unchanged);
+
+        let fake = fake.with_your_ip("192.0.2.2");
+        let updates = updater.update(&fake).unwrap();
+        assert!(matches!(updates[0].action, Action::Edited { .. }));
+
+        let records = fake.records("example.com").unwrap();
+        assert_eq!(records.len(), 2);
+        let home = records
+            .iter()
+            .find(|record| record.name == "home.example.com")
+            .unwrap();
+        assert_eq!(home.record_type, "A");
+        assert_eq!(home.content, "192.0.2.2");
+    }
+
+    #[test]
+    fn existing_record() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_your_ip("2001:db8::1");
+        let mut updater = Updater::new(vec![home()]);
+        updater.update(&fake).unwrap();
+
+        // A fresh updater finds the record and leaves it alone.
+        let mut updater = Updater::new(vec![home()]);
+        let updates = updater.update(&fake).unwrap();
+        assert_eq!(updates[0].action, Action::Unchanged);
+        assert_eq!(fake.records("example.com").unwrap()[0].record_type, "AAAA");
+    }
+
+    #[test]
+    fn state_file_skips_api_calls() {
+        let dir = env::temp_dir().join(format!("porkbun-ddns-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        let _ = fs::remove_file(&path);
+
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_your_ip("192.0.2.1");
+        Updater::new(vec![home()])
+            .with_state_file(&path)
+            .unwrap()
+            .update(&fake)
+            .unwrap();
+
+        // Only `ping` is expected: the state says the record is up to date.
+        let client = TestClient::new().expect_json(
+            ExpectedUrl::builder()
+                .method(Method::POST)
+                .endpoint("ping")
+                .content_type("application/json")
+                .body_json(&json!({}))
+                .build()
+                .unwrap(),
+            &json!({ "status": "SUCCESS", "yourIp": "192.0.2.1" }),
+        );
+        let updates = Updater::new(vec![home()])
+            .with_state_file(&path)
+            .unwrap()
+            .update(&client)
+            .unwrap();
+        assert_eq!(updates[0].action, Action::Unchanged);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_address() {
+        let fake = FakePorkbun::new().with_your_ip("not an address");
+        let err = Updater::new(vec![home()]).update(&fake).unwrap_err();
+        assert!(matches!(err, DdnsError::Address { .. }));
+    }
+
+    #[test]
+    fn run_until_shutdown() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_your_ip("192.0.2.1");
+        let (shutdown, receiver) = mpsc::channel();
+        shutdown.send(()).unwrap();
+
+        Updater::new(vec![home()]).run(&fake, Duration::from_secs(3600), &receiver);
+        assert_eq!(fake.records("example.com").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn config() {
+        let config: Config = toml::from_str(
+            r#"
+            state = "/var/lib/porkbun-ddns/state.json"
+
+            [[hosts]]
+            domain = "example.com"
+            name = "home"
+
+            [[hosts]]
+            domain = "example.org"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.interval, 300);
+        assert_eq!(config.hosts[0], home());
+        assert_eq!(config.hosts[1].fqdn(), "example.org");
+    }
+}
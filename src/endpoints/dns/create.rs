@@ -1,4 +1,7 @@
-use crate::api::Endpoint;
+use crate::{
+    api::Endpoint,
+    name::{domain_segment, normalize_domain, relative_record_name, validate_names},
+};
 use derive_builder::Builder;
 use http::Method;
 use serde_json::{Map, Value};
@@ -7,16 +10,16 @@ use std::borrow::Cow;
 use super::{fill_body_with_record, DnsContent};
 
 #[derive(Debug, Builder)]
-#[builder(setter(strip_option))]
+#[builder(setter(strip_option), build_fn(validate = "Self::validate"))]
 pub struct CreateDns<'a> {
     #[builder(setter(into))]
-    record: DnsContent,
+    pub(crate) record: DnsContent,
     #[builder(setter(into))]
-    domain: Cow<'a, str>,
+    pub(crate) domain: Cow<'a, str>,
     #[builder(default)]
-    ttl: Option<u32>,
+    pub(crate) ttl: Option<u32>,
     #[builder(setter(into), default)]
-    name: Option<Cow<'a, str>>,
+    pub(crate) name: Option<Cow<'a, str>>,
 }
 
 impl<'a> CreateDns<'a> {
@@ -25,19 +28,35 @@ impl<'a> CreateDns<'a> {
     }
 }
 
+impl<'a> CreateDnsBuilder<'a> {
+    fn validate(&self) -> Result<(), String> {
+        validate_names(
+            self.domain.as_deref(),
+            self.name.as_ref().and_then(|name| name.as_deref()),
+        )
+    }
+}
+
 impl<'a> Endpoint for CreateDns<'a> {
     fn method(&self) -> Method {
         Method::POST
     }
 
     fn endpoint(&self) -> Cow<'static, str> {
-        format!("dns/create/{}", self.domain).into()
+        format!("dns/create/{}", domain_segment(&self.domain)).into()
+    }
+
+    fn domain(&self) -> Option<Cow<'_, str>> {
+        Some(normalize_domain(&self.domain))
     }
 
     fn body(&self) -> Map<String, Value> {
         let mut body = Map::default();
         if let Some(name) = &self.name {
-            body.insert("name".into(), name.to_string().into());
+            body.insert(
+                "name".into(),
+                relative_record_name(name, &self.domain).into(),
+            );
         }
 
         if let Some(ttl) = self.ttl {
@@ -50,14 +69,14 @@ impl<'a> Endpoint for CreateDns<'a> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sync", feature = "async"))]
 mod tests {
     use http::Method;
     use serde_json::json;
 
     use crate::{
-        api::{self, Query},
-        endpoints::{CreateDns, DnsContent},
+        api::{self, Endpoint, Query},
+        endpoints::{CreateDns, CreateDnsBuilderError, DnsContent},
         test::client::{ExpectedUrl, SingleTestClient},
     };
 
@@ -89,6 +108,51 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn domain_is_validated() {
+        let long = format!("{}.com", "a".repeat(64));
+        for domain in [
+            " ",
+            "example..com",
+            long.as_str(),
+            "-example.com",
+            "example.com/1",
+        ] {
+            let err = CreateDns::builder()
+                .domain(domain)
+                .record(DnsContent::A {
+                    content: "192.0.2.1".parse().unwrap(),
+                })
+                .build()
+                .unwrap_err();
+            assert!(
+                matches!(err, CreateDnsBuilderError::ValidationError(_)),
+                "{}",
+                domain
+            );
+        }
+    }
+
+    #[test]
+    fn name_is_validated() {
+        let long = "a".repeat(64);
+        for name in ["www..dev", long.as_str(), "a b", "www/", "www.example.org."] {
+            let err = CreateDns::builder()
+                .domain("example.com")
+                .name(name)
+                .record(DnsContent::A {
+                    content: "192.0.2.1".parse().unwrap(),
+                })
+                .build()
+                .unwrap_err();
+            assert!(
+                matches!(err, CreateDnsBuilderError::ValidationError(_)),
+                "{}",
+                name
+            );
+        }
+    }
+
     #[test]
     fn endpoint() {
         let endpoint = ExpectedUrl::builder()
@@ -119,4 +183,35 @@ mod tests {
 
         api::ignore(endpoint).query(&client).unwrap();
     }
+
+    #[test]
+    fn normalized_names() {
+        let endpoint = CreateDns::builder()
+            .domain(" Example.COM. ")
+            .record(DnsContent::A {
+                content: "192.0.2.1".parse().unwrap(),
+            })
+            .name("WWW.example.com.")
+            .build()
+            .unwrap();
+        assert_eq!(endpoint.endpoint(), "dns/create/example.com");
+        assert_eq!(endpoint.domain().unwrap(), "example.com");
+        assert_eq!(endpoint.body()["name"], "www");
+    }
+
+    #[cfg(feature = "idna")]
+    #[test]
+    fn internationalized_names() {
+        let endpoint = CreateDns::builder()
+            .domain(" Bücher.Example. ")
+            .record(DnsContent::A {
+                content: "192.0.2.1".parse().unwrap(),
+            })
+            .name("WWW.bücher.example.")
+            .build()
+            .unwrap();
+        assert_eq!(endpoint.endpoint(), "dns/create/xn--bcher-kva.example");
+        assert_eq!(endpoint.domain().unwrap(), "xn--bcher-kva.example");
+        assert_eq!(endpoint.body()["name"], "www");
+    }
 }
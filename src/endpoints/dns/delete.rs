@@ -1,9 +1,13 @@
-use crate::api::Endpoint;
+use crate::{
+    api::Endpoint,
+    name::{domain_segment, normalize_domain, path_segment, validate_names},
+};
 use derive_builder::Builder;
 use http::Method;
 use std::borrow::Cow;
 
 #[derive(Debug, Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
 pub struct DeleteDns<'a> {
     #[builder(setter(into))]
     id: Cow<'a, str>,
@@ -17,22 +21,37 @@ impl<'a> DeleteDns<'a> {
     }
 }
 
+impl<'a> DeleteDnsBuilder<'a> {
+    fn validate(&self) -> Result<(), String> {
+        validate_names(self.domain.as_deref(), None)
+    }
+}
+
 impl<'a> Endpoint for DeleteDns<'a> {
     fn method(&self) -> Method {
         Method::POST
     }
 
     fn endpoint(&self) -> Cow<'static, str> {
-        format!("dns/delete/{}/{}", self.domain, self.id).into()
+        format!(
+            "dns/delete/{}/{}",
+            domain_segment(&self.domain),
+            path_segment(&self.id)
+        )
+        .into()
+    }
+
+    fn domain(&self) -> Option<Cow<'_, str>> {
+        Some(normalize_domain(&self.domain))
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sync", feature = "async"))]
 mod tests {
     use http::Method;
 
     use crate::{
-        api::{self, Query},
+        api::{self, Endpoint, Query},
         endpoints::DeleteDns,
         test::client::{ExpectedUrl, SingleTestClient},
     };
@@ -58,6 +77,29 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn escaped_path() {
+        let endpoint = DeleteDns::builder()
+            .id("1/2")
+            .domain("example.com")
+            .build()
+            .unwrap();
+        assert_eq!(endpoint.endpoint(), "dns/delete/example.com/1%2F2");
+    }
+
+    #[test]
+    fn domain_is_validated() {
+        let err = DeleteDns::builder()
+            .id("1")
+            .domain("example.com/1")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#""example.com/1" has an invalid label "com/1""#
+        );
+    }
+
     #[test]
     fn endpoint() {
         let endpoint = ExpectedUrl::builder()
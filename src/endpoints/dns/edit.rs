@@ -1,12 +1,15 @@
 use super::{fill_body_with_record, DnsContent};
-use crate::api::Endpoint;
+use crate::{
+    api::Endpoint,
+    name::{domain_segment, normalize_domain, path_segment, relative_record_name, validate_names},
+};
 use derive_builder::Builder;
 use http::Method;
 use serde_json::{Map, Value};
 use std::borrow::Cow;
 
 #[derive(Debug, Builder)]
-#[builder(setter(strip_option))]
+#[builder(setter(strip_option), build_fn(validate = "Self::validate"))]
 pub struct EditDns<'a> {
     #[builder(setter(into))]
     record: DnsContent,
@@ -26,19 +29,40 @@ impl<'a> EditDns<'a> {
     }
 }
 
+impl<'a> EditDnsBuilder<'a> {
+    fn validate(&self) -> Result<(), String> {
+        validate_names(
+            self.domain.as_deref(),
+            self.name.as_ref().and_then(|name| name.as_deref()),
+        )
+    }
+}
+
 impl<'a> Endpoint for EditDns<'a> {
     fn method(&self) -> Method {
         Method::POST
     }
 
     fn endpoint(&self) -> Cow<'static, str> {
-        format!("dns/edit/{}/{}", self.domain, self.id).into()
+        format!(
+            "dns/edit/{}/{}",
+            domain_segment(&self.domain),
+            path_segment(&self.id)
+        )
+        .into()
+    }
+
+    fn domain(&self) -> Option<Cow<'_, str>> {
+        Some(normalize_domain(&self.domain))
     }
 
     fn body(&self) -> Map<String, Value> {
         let mut body = Map::default();
         if let Some(name) = &self.name {
-            body.insert("name".into(), name.to_string().into());
+            body.insert(
+                "name".into(),
+                relative_record_name(name, &self.domain).into(),
+            );
         }
 
         if let Some(ttl) = self.ttl {
@@ -51,13 +75,13 @@ impl<'a> Endpoint for EditDns<'a> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sync", feature = "async"))]
 mod tests {
     use http::Method;
     use serde_json::json;
 
     use crate::{
-        api::{self, Query},
+        api::{self, Endpoint, Query},
         endpoints::{DnsContent, EditDns},
         test::client::{ExpectedUrl, SingleTestClient},
     };
@@ -82,7 +106,7 @@ mod tests {
     #[test]
     fn id_is_necessary() {
         let err = EditDns::builder()
-            .domain("")
+            .domain("example.com")
             .record(DnsContent::Cname {
                 content: "".to_string(),
             })
@@ -134,4 +158,45 @@ mod tests {
 
         api::ignore(endpoint).query(&client).unwrap();
     }
+
+    #[test]
+    fn name_forms() {
+        for (name, expected) in [
+            ("www", "www"),
+            ("www.example.com", "www"),
+            ("WWW.Example.com.", "www"),
+            ("@", ""),
+            ("example.com.", ""),
+        ] {
+            let endpoint = EditDns::builder()
+                .id("1234")
+                .domain("example.com")
+                .name(name)
+                .record(DnsContent::A {
+                    content: "192.0.2.1".parse().unwrap(),
+                })
+                .build()
+                .unwrap();
+            assert_eq!(endpoint.body()["name"], expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn names_are_validated() {
+        let builder = |domain: &str, name: &str| {
+            EditDns::builder()
+                .id("1234")
+                .domain(domain)
+                .name(name)
+                .record(DnsContent::A {
+                    content: "192.0.2.1".parse().unwrap(),
+                })
+                .build()
+                .map(|_| ())
+        };
+        assert!(builder("example.com", "www").is_ok());
+        assert!(builder("example..com", "www").is_err());
+        assert!(builder("example.com", "www..dev").is_err());
+        assert!(builder("example.com", "www.*").is_err());
+    }
 }
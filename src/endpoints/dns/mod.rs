@@ -1,5 +1,7 @@
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::net::{Ipv4Addr, Ipv6Addr};
+use thiserror::Error;
 
 mod create;
 mod delete;
@@ -11,7 +13,12 @@ pub use self::delete::*;
 pub use self::edit::*;
 pub use self::retrieve::*;
 
-#[derive(Debug, Clone)]
+/// The type and content of a record.
+///
+/// With serde, the type is written as a `type` field next to the others, as in
+/// `{"type": "MX", "content": "mail.example.com", "priority": 10}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "UPPERCASE")]
 pub enum DnsContent {
     Ns { content: String },
     A { content: Ipv4Addr },
@@ -24,45 +31,179 @@ pub enum DnsContent {
     Srv { content: String, priority: u16 },
 }
 
-pub(crate) fn fill_body_with_record(body: &mut Map<String, Value>, record: &DnsContent) {
-    match record {
-        DnsContent::Ns { content } => {
-            body.insert("type".into(), "NS".into());
-            body.insert("content".into(), content.to_string().into());
-        }
-        DnsContent::A { content } => {
-            body.insert("type".into(), "A".into());
-            body.insert("content".into(), content.to_string().into());
-        }
-        DnsContent::Txt { content } => {
-            body.insert("type".into(), "TXT".into());
-            body.insert("content".into(), content.to_string().into());
-        }
-        DnsContent::Caa { content } => {
-            body.insert("type".into(), "CAA".into());
-            body.insert("content".into(), content.to_string().into());
-        }
-        DnsContent::Tlsa { content } => {
-            body.insert("type".into(), "TLSA".into());
-            body.insert("content".into(), content.to_string().into());
-        }
-        DnsContent::Cname { content } => {
-            body.insert("type".into(), "CNAME".into());
-            body.insert("content".into(), content.to_string().into());
+/// Errors which may occur when building a [`DnsContent`] from its parts.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DnsContentError {
+    /// The record type is not supported.
+    #[error("unsupported record type `{}`", record_type)]
+    UnsupportedType {
+        /// The record type.
+        record_type: String,
+    },
+    /// The content is not valid for the record type.
+    #[error("invalid {} record content `{}`", record_type, content)]
+    InvalidContent {
+        /// The record type.
+        record_type: &'static str,
+        /// The content.
+        content: String,
+    },
+    /// The record type needs a priority.
+    #[error("{} records need a priority", record_type)]
+    MissingPriority {
+        /// The record type.
+        record_type: &'static str,
+    },
+}
+
+impl DnsContent {
+    /// Build a record from its type, as written in DNS (case-insensitive), its content and its
+    /// priority, which is only used by `MX` and `SRV` records.
+    pub fn new(
+        record_type: &str,
+        content: &str,
+        priority: Option<u16>,
+    ) -> Result<Self, DnsContentError> {
+        let content = content.to_string();
+        let priority =
+            |record_type| priority.ok_or(DnsContentError::MissingPriority { record_type });
+
+        Ok(match record_type.to_ascii_uppercase().as_str() {
+            "NS" => DnsContent::Ns { content },
+            "A" => DnsContent::A {
+                content: content
+                    .parse()
+                    .map_err(|_| DnsContentError::InvalidContent {
+                        record_type: "A",
+                        content,
+                    })?,
+            },
+            "TXT" => DnsContent::Txt { content },
+            "CAA" => DnsContent::Caa { content },
+            "TLSA" => DnsContent::Tlsa { content },
+            "CNAME" => DnsContent::Cname { content },
+            "AAAA" => DnsContent::Aaaa {
+                content: content
+                    .parse()
+                    .map_err(|_| DnsContentError::InvalidContent {
+                        record_type: "AAAA",
+                        content,
+                    })?,
+            },
+            "MX" => DnsContent::Mx {
+                content,
+                priority: priority("MX")?,
+            },
+            "SRV" => DnsContent::Srv {
+                content,
+                priority: priority("SRV")?,
+            },
+            _ => {
+                return Err(DnsContentError::UnsupportedType {
+                    record_type: record_type.into(),
+                })
+            }
+        })
+    }
+
+    /// The type of the record, as written in DNS.
+    pub fn record_type(&self) -> &'static str {
+        match self {
+            DnsContent::Ns { .. } => "NS",
+            DnsContent::A { .. } => "A",
+            DnsContent::Txt { .. } => "TXT",
+            DnsContent::Caa { .. } => "CAA",
+            DnsContent::Tlsa { .. } => "TLSA",
+            DnsContent::Cname { .. } => "CNAME",
+            DnsContent::Aaaa { .. } => "AAAA",
+            DnsContent::Mx { .. } => "MX",
+            DnsContent::Srv { .. } => "SRV",
         }
-        DnsContent::Aaaa { content } => {
-            body.insert("type".into(), "AAAA".into());
-            body.insert("content".into(), content.to_string().into());
+    }
+
+    /// The content of the record, as sent to Porkbun.
+    pub fn content(&self) -> String {
+        match self {
+            DnsContent::A { content } => content.to_string(),
+            DnsContent::Aaaa { content } => content.to_string(),
+            DnsContent::Ns { content }
+            | DnsContent::Txt { content }
+            | DnsContent::Caa { content }
+            | DnsContent::Tlsa { content }
+            | DnsContent::Cname { content }
+            | DnsContent::Mx { content, .. }
+            | DnsContent::Srv { content, .. } => content.clone(),
         }
-        DnsContent::Mx { content, priority } => {
-            body.insert("type".into(), "MX".into());
-            body.insert("prio".into(), priority.to_string().into());
-            body.insert("content".into(), content.to_string().into());
+    }
+
+    /// The priority of the record, for the types which have one.
+    pub fn priority(&self) -> Option<u16> {
+        match self {
+            DnsContent::Mx { priority, .. } | DnsContent::Srv { priority, .. } => Some(*priority),
+            _ => None,
         }
-        DnsContent::Srv { content, priority } => {
-            body.insert("type".into(), "SRV".into());
-            body.insert("prio".into(), priority.to_string().into());
-            body.insert("content".into(), content.to_string().into());
+    }
+}
+
+pub(crate) fn fill_body_with_record(body: &mut Map<String, Value>, record: &DnsContent) {
+    body.insert("type".into(), record.record_type().into());
+    if let Some(priority) = record.priority() {
+        body.insert("prio".into(), priority.to_string().into());
+    }
+    body.insert("content".into(), record.content().into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DnsContent, DnsContentError};
+
+    #[test]
+    fn new_round_trip() {
+        let record = DnsContent::new("mx", "mail.example.com", Some(10)).unwrap();
+        assert_eq!(record.record_type(), "MX");
+        assert_eq!(record.content(), "mail.example.com");
+        assert_eq!(record.priority(), Some(10));
+
+        let record = DnsContent::new("AAAA", "2001:db8::1", Some(10)).unwrap();
+        assert_eq!(record.record_type(), "AAAA");
+        assert_eq!(record.content(), "2001:db8::1");
+        assert_eq!(record.priority(), None);
+    }
+
+    #[test]
+    fn serde() {
+        let record = DnsContent::Mx {
+            content: "mail.example.com".into(),
+            priority: 10,
+        };
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"type": "MX", "content": "mail.example.com", "priority": 10}),
+        );
+        assert_eq!(serde_json::from_value::<DnsContent>(value).unwrap(), record);
+    }
+
+    #[test]
+    fn new_invalid_address() {
+        let err = DnsContent::new("A", "2001:db8::1", None).unwrap_err();
+        assert_eq!(err.to_string(), "invalid A record content `2001:db8::1`");
+    }
+
+    #[test]
+    fn new_missing_priority() {
+        let err = DnsContent::new("SRV", "0 5060 sip.example.com", None).unwrap_err();
+        if let DnsContentError::MissingPriority { record_type } = err {
+            assert_eq!(record_type, "SRV");
+        } else {
+            panic!("unexpected error: {}", err);
         }
-    };
+    }
+
+    #[test]
+    fn new_unsupported_type() {
+        let err = DnsContent::new("HINFO", "", None).unwrap_err();
+        assert_eq!(err.to_string(), "unsupported record type `HINFO`");
+    }
 }
@@ -1,9 +1,13 @@
-use crate::api::Endpoint;
+use crate::{
+    api::Endpoint,
+    name::{domain_segment, normalize_domain, validate_names},
+};
 use derive_builder::Builder;
 use http::Method;
 use std::borrow::Cow;
 
 #[derive(Debug, Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
 pub struct RetrieveDns<'a> {
     #[builder(setter(into))]
     domain: Cow<'a, str>,
@@ -15,17 +19,27 @@ impl<'a> RetrieveDns<'a> {
     }
 }
 
+impl<'a> RetrieveDnsBuilder<'a> {
+    fn validate(&self) -> Result<(), String> {
+        validate_names(self.domain.as_deref(), None)
+    }
+}
+
 impl<'a> Endpoint for RetrieveDns<'a> {
     fn method(&self) -> Method {
         Method::POST
     }
 
     fn endpoint(&self) -> Cow<'static, str> {
-        format!("dns/retrieve/{}", self.domain).into()
+        format!("dns/retrieve/{}", domain_segment(&self.domain)).into()
+    }
+
+    fn domain(&self) -> Option<Cow<'_, str>> {
+        Some(normalize_domain(&self.domain))
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sync", feature = "async"))]
 mod tests {
     use http::Method;
 
@@ -49,6 +63,14 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn domain_is_validated() {
+        for domain in ["", "example", "example..com", "exa mple.com"] {
+            let err = RetrieveDns::builder().domain(domain).build();
+            assert!(err.is_err(), "{}", domain);
+        }
+    }
+
     #[test]
     fn endpoint() {
         let endpoint = ExpectedUrl::builder()
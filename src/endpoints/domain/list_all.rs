@@ -0,0 +1,97 @@
+use crate::api::Endpoint;
+use derive_builder::Builder;
+use http::Method;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The most domains Porkbun returns at once.
+pub const LIST_ALL_PAGE_SIZE: usize = 1000;
+
+/// List the domains of the account, [`LIST_ALL_PAGE_SIZE`] at a time.
+#[derive(Debug, Builder)]
+pub struct ListAllDomains {
+    /// The index of the first domain to return.
+    #[builder(default)]
+    start: usize,
+    /// Whether to include the labels of the domains.
+    #[builder(default)]
+    include_labels: bool,
+}
+
+impl ListAllDomains {
+    pub fn builder() -> ListAllDomainsBuilder {
+        ListAllDomainsBuilder::default()
+    }
+}
+
+impl Endpoint for ListAllDomains {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        "domain/listAll".into()
+    }
+
+    fn body(&self) -> Map<String, Value> {
+        let mut body = Map::default();
+        body.insert("start".into(), self.start.to_string().into());
+        if self.include_labels {
+            body.insert("includeLabels".into(), "yes".into());
+        }
+        body
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use http::Method;
+    use serde_json::json;
+
+    use crate::{
+        api::{self, Query},
+        endpoints::ListAllDomains,
+        test::client::{ExpectedUrl, SingleTestClient},
+    };
+
+    #[test]
+    fn defaults_are_sufficient() {
+        ListAllDomains::builder().build().unwrap();
+    }
+
+    #[test]
+    fn endpoint() {
+        let endpoint = ExpectedUrl::builder()
+            .method(Method::POST)
+            .endpoint("domain/listAll")
+            .content_type("application/json")
+            .body_json(&json!({ "start": "0" }))
+            .build()
+            .unwrap();
+        let client = SingleTestClient::new_raw(endpoint, "");
+
+        let endpoint = ListAllDomains::builder().build().unwrap();
+
+        api::ignore(endpoint).query(&client).unwrap();
+    }
+
+    #[test]
+    fn endpoint_labels() {
+        let endpoint = ExpectedUrl::builder()
+            .method(Method::POST)
+            .endpoint("domain/listAll")
+            .content_type("application/json")
+            .body_json(&json!({ "start": "1000", "includeLabels": "yes" }))
+            .build()
+            .unwrap();
+        let client = SingleTestClient::new_raw(endpoint, "");
+
+        let endpoint = ListAllDomains::builder()
+            .start(1000)
+            .include_labels(true)
+            .build()
+            .unwrap();
+
+        api::ignore(endpoint).query(&client).unwrap();
+    }
+}
@@ -0,0 +1,3 @@
+mod list_all;
+
+pub use self::list_all::*;
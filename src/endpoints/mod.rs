@@ -1,4 +1,6 @@
 mod dns;
+mod domain;
 mod ping;
 pub use self::dns::*;
+pub use self::domain::*;
 pub use self::ping::*;
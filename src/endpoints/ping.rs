@@ -23,7 +23,7 @@ impl Endpoint for Ping {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sync", feature = "async"))]
 mod tests {
     use http::Method;
 
@@ -1,13 +1,51 @@
+#[cfg(feature = "sync")]
+pub mod acme;
 pub mod api;
+#[cfg(any(feature = "sync", feature = "async"))]
 pub mod auth;
+#[cfg(feature = "backup")]
+pub mod backup;
+#[cfg(any(feature = "sync", feature = "async"))]
+pub mod cache;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(all(feature = "sync", feature = "config"))]
+pub mod ddns;
 pub mod endpoints;
-#[cfg(feature = "client_api")]
+#[cfg(any(
+    feature = "client_api",
+    feature = "ureq-client",
+    feature = "hyper-client"
+))]
+pub mod middleware;
+pub mod name;
+#[cfg(any(
+    feature = "client_api",
+    feature = "ureq-client",
+    feature = "hyper-client"
+))]
 mod porkbun;
+#[cfg(any(feature = "sync", feature = "async"))]
+pub mod sync;
+#[cfg(feature = "sync")]
+pub mod transaction;
 pub mod types;
+#[cfg(feature = "zone")]
+pub mod zone;
 
+#[cfg(feature = "hyper-client")]
+pub use self::porkbun::HyperPorkbun;
+#[cfg(feature = "ureq-client")]
+pub use self::porkbun::UreqPorkbun;
 #[cfg(feature = "client_api")]
-pub use self::porkbun::{AsyncPorkbun, Porkbun, PorkbunError};
+pub use self::porkbun::{AsyncPorkbun, Porkbun};
+#[cfg(any(
+    feature = "client_api",
+    feature = "ureq-client",
+    feature = "hyper-client"
+))]
+pub use self::porkbun::{PorkbunError, RestError};
 pub use crate::types::*;
 
-#[cfg(test)]
-mod test;
+#[cfg(any(all(test, feature = "sync", feature = "async"), feature = "test-util"))]
+pub mod test;
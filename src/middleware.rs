@@ -0,0 +1,339 @@
+//! Hooks around the requests sent by the clients: blocking middleware for `Porkbun` and
+//! `UreqPorkbun`, asynchronous middleware for `AsyncPorkbun` and `HyperPorkbun`.
+//!
+//! Middleware sees every request before it is sent and every response after it is received.
+//! It may modify either, or answer a request itself without calling the rest of the chain.
+//! Middleware runs in the order it was added: the first one sees the request first and the
+//! response last.
+
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{Method, Request, Response, Uri};
+use log::debug;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::porkbun::RestError;
+
+type RestResult = Result<Response<Bytes>, RestError>;
+
+#[cfg(feature = "async")]
+/// A future returned by the asynchronous middleware chain.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+#[cfg(feature = "sync")]
+type SendFn<'a> = dyn Fn(Request<Vec<u8>>) -> RestResult + Sync + 'a;
+#[cfg(feature = "async")]
+type AsyncSendFn<'a> = dyn Fn(Request<Vec<u8>>) -> BoxFuture<'a, RestResult> + Send + Sync + 'a;
+
+#[cfg(feature = "sync")]
+/// Middleware for the blocking clients.
+pub trait Middleware: Send + Sync {
+    /// Handle a request, usually by passing it on to `next`.
+    fn handle(&self, request: Request<Vec<u8>>, next: Next<'_>) -> RestResult;
+}
+
+#[cfg(feature = "sync")]
+/// The rest of a blocking middleware chain.
+pub struct Next<'a> {
+    middleware: &'a [Arc<dyn Middleware>],
+    send: &'a SendFn<'a>,
+}
+
+#[cfg(feature = "sync")]
+impl<'a> Next<'a> {
+    pub(crate) fn new(middleware: &'a [Arc<dyn Middleware>], send: &'a SendFn<'a>) -> Self {
+        Self { middleware, send }
+    }
+
+    /// Pass the request to the rest of the chain and eventually to Porkbun.
+    pub fn run(self, request: Request<Vec<u8>>) -> RestResult {
+        match self.middleware.split_first() {
+            Some((middleware, rest)) => middleware.handle(request, Next::new(rest, self.send)),
+            None => (self.send)(request),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+/// Middleware for the asynchronous clients.
+#[async_trait]
+pub trait AsyncMiddleware: Send + Sync {
+    /// Handle a request, usually by passing it on to `next`.
+    async fn handle(&self, request: Request<Vec<u8>>, next: AsyncNext<'_>) -> RestResult;
+}
+
+#[cfg(feature = "async")]
+/// The rest of an asynchronous middleware chain.
+pub struct AsyncNext<'a> {
+    middleware: &'a [Arc<dyn AsyncMiddleware>],
+    send: &'a AsyncSendFn<'a>,
+}
+
+#[cfg(feature = "async")]
+impl<'a> AsyncNext<'a> {
+    pub(crate) fn new(
+        middleware: &'a [Arc<dyn AsyncMiddleware>],
+        send: &'a AsyncSendFn<'a>,
+    ) -> Self {
+        Self { middleware, send }
+    }
+
+    /// Pass the request to the rest of the chain and eventually to Porkbun.
+    pub fn run(self, request: Request<Vec<u8>>) -> BoxFuture<'a, RestResult> {
+        match self.middleware.split_first() {
+            Some((middleware, rest)) => middleware.handle(request, AsyncNext::new(rest, self.send)),
+            None => (self.send)(request),
+        }
+    }
+}
+
+/// Log the method and URL of every request and the status of every response.
+///
+/// Bodies are not logged since they carry the credentials.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Logging;
+
+impl Logging {
+    fn request(request: &Request<Vec<u8>>) {
+        debug!(target: "porkbun", "sending {} {}", request.method(), request.uri());
+    }
+
+    fn response(method: &Method, uri: &Uri, result: &RestResult) {
+        match result {
+            Ok(rsp) => debug!(target: "porkbun", "{} {}: {}", method, uri, rsp.status()),
+            Err(err) => debug!(target: "porkbun", "{} {}: {}", method, uri, err),
+        }
+    }
+}
+
+#[cfg(feature = "sync")]
+impl Middleware for Logging {
+    fn handle(&self, request: Request<Vec<u8>>, next: Next<'_>) -> RestResult {
+        Self::request(&request);
+        let (method, uri) = (request.method().clone(), request.uri().clone());
+        let result = next.run(request);
+        Self::response(&method, &uri, &result);
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncMiddleware for Logging {
+    async fn handle(&self, request: Request<Vec<u8>>, next: AsyncNext<'_>) -> RestResult {
+        Self::request(&request);
+        let (method, uri) = (request.method().clone(), request.uri().clone());
+        let result = next.run(request).await;
+        Self::response(&method, &uri, &result);
+        result
+    }
+}
+
+/// Report how long each request took, including the middleware added after this one.
+pub struct Timing<F> {
+    report: F,
+}
+
+impl<F> Timing<F>
+where
+    F: Fn(&Uri, Duration) + Send + Sync,
+{
+    /// Call `report` with the URL and the duration of every request.
+    pub fn new(report: F) -> Self {
+        Self { report }
+    }
+}
+
+#[cfg(feature = "sync")]
+impl<F> Middleware for Timing<F>
+where
+    F: Fn(&Uri, Duration) + Send + Sync,
+{
+    fn handle(&self, request: Request<Vec<u8>>, next: Next<'_>) -> RestResult {
+        let uri = request.uri().clone();
+        let start = Instant::now();
+        let result = next.run(request);
+        (self.report)(&uri, start.elapsed());
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<F> AsyncMiddleware for Timing<F>
+where
+    F: Fn(&Uri, Duration) + Send + Sync,
+{
+    async fn handle(&self, request: Request<Vec<u8>>, next: AsyncNext<'_>) -> RestResult {
+        let uri = request.uri().clone();
+        let start = Instant::now();
+        let result = next.run(request).await;
+        (self.report)(&uri, start.elapsed());
+        result
+    }
+}
+
+#[cfg(all(test, feature = "client_api"))]
+mod tests {
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use http::{HeaderValue, Request, Response, StatusCode};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        api::{AsyncQuery, Query},
+        auth::Auth,
+        endpoints::Ping,
+        AsyncPorkbun, PingResponse, Porkbun,
+    };
+
+    use super::{AsyncMiddleware, AsyncNext, Middleware, Next, RestResult, Timing};
+
+    /// Record the order in which the chain is walked.
+    struct Trace {
+        name: &'static str,
+        trace: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for Trace {
+        fn handle(&self, mut request: Request<Vec<u8>>, next: Next<'_>) -> RestResult {
+            self.trace.lock().unwrap().push(format!("> {}", self.name));
+            request
+                .headers_mut()
+                .append("x-trace", HeaderValue::from_static(self.name));
+            let result = next.run(request);
+            self.trace.lock().unwrap().push(format!("< {}", self.name));
+            result
+        }
+    }
+
+    #[async_trait]
+    impl AsyncMiddleware for Trace {
+        async fn handle(&self, request: Request<Vec<u8>>, next: AsyncNext<'_>) -> RestResult {
+            self.trace.lock().unwrap().push(format!("> {}", self.name));
+            let result = next.run(request).await;
+            self.trace.lock().unwrap().push(format!("< {}", self.name));
+            result
+        }
+    }
+
+    /// Answer every request without sending it.
+    struct Canned;
+
+    impl Canned {
+        fn response(request: &Request<Vec<u8>>) -> RestResult {
+            assert_eq!(request.uri().path(), "/api/json/v3/ping");
+            let body = json!({
+                "status": "SUCCESS",
+                "yourIp": "127.0.0.1",
+            });
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(Bytes::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap())
+        }
+    }
+
+    impl Middleware for Canned {
+        fn handle(&self, request: Request<Vec<u8>>, _: Next<'_>) -> RestResult {
+            Self::response(&request)
+        }
+    }
+
+    #[async_trait]
+    impl AsyncMiddleware for Canned {
+        async fn handle(&self, request: Request<Vec<u8>>, _: AsyncNext<'_>) -> RestResult {
+            Self::response(&request)
+        }
+    }
+
+    fn auth() -> Auth {
+        Auth::new("apikey".into(), "apisecret".into())
+    }
+
+    #[test]
+    fn chain_order() {
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let middleware: Vec<Arc<dyn Middleware>> = vec![
+            Arc::new(Trace {
+                name: "first",
+                trace: trace.clone(),
+            }),
+            Arc::new(Trace {
+                name: "second",
+                trace: trace.clone(),
+            }),
+        ];
+        let send = |request: Request<Vec<u8>>| {
+            let headers = request
+                .headers()
+                .get_all("x-trace")
+                .iter()
+                .map(|value| value.to_str().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(headers, ["first", "second"]);
+            Ok(Response::new(Bytes::new()))
+        };
+
+        Next::new(&middleware, &send)
+            .run(Request::new(Vec::new()))
+            .unwrap();
+
+        assert_eq!(
+            *trace.lock().unwrap(),
+            ["> first", "> second", "< second", "< first"],
+        );
+    }
+
+    #[test]
+    fn short_circuit() {
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let timings = Arc::new(Mutex::new(Vec::new()));
+        let client = Porkbun::new(auth())
+            .unwrap()
+            .with_middleware(Trace {
+                name: "outer",
+                trace: trace.clone(),
+            })
+            .with_middleware(Timing::new({
+                let timings = timings.clone();
+                move |uri: &http::Uri, _| timings.lock().unwrap().push(uri.path().to_string())
+            }))
+            .with_middleware(Canned);
+
+        let rsp: PingResponse = Ping::builder().build().unwrap().query(&client).unwrap();
+
+        assert_eq!(rsp.your_ip, "127.0.0.1");
+        assert_eq!(*trace.lock().unwrap(), ["> outer", "< outer"]);
+        assert_eq!(*timings.lock().unwrap(), ["/api/json/v3/ping"]);
+    }
+
+    #[tokio::test]
+    async fn short_circuit_async() {
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let client = AsyncPorkbun::new(auth())
+            .unwrap()
+            .with_middleware(Trace {
+                name: "outer",
+                trace: trace.clone(),
+            })
+            .with_middleware(Canned);
+
+        let rsp: PingResponse = Ping::builder()
+            .build()
+            .unwrap()
+            .query_async(&client)
+            .await
+            .unwrap();
+
+        assert_eq!(rsp.your_ip, "127.0.0.1");
+        assert_eq!(*trace.lock().unwrap(), ["> outer", "< outer"]);
+    }
+}
@@ -0,0 +1,612 @@
+//! Domain and record names.
+//!
+//! Porkbun takes domains in the path of its endpoints and record names in their bodies, both in
+//! their ASCII form. [`DomainName`] and [`RecordName`] check names once and keep them
+//! normalized: lowercase, without a trailing dot, with internationalized labels converted to
+//! punycode. They display in Unicode.
+//!
+//! Converting internationalized names needs the `idna` feature; without it, only names which
+//! are already in ASCII are accepted, and they display as such.
+//!
+//! Porkbun takes record names relative to their domain, as in `www`, but reports them fully
+//! qualified, as in `www.example.com`; the domain itself is an empty name in the former and the
+//! domain in the latter. [`RecordName`] is always relative: [`RecordName::from_fqdn`] and
+//! [`RecordName::to_fqdn`] convert between the forms, and [`RecordName::in_zone`] accepts
+//! either.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    convert::TryFrom,
+    fmt::{self, Display, Write},
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// The longest a label may be, in ASCII.
+const MAX_LABEL_LENGTH: usize = 63;
+/// The longest a name may be, in ASCII and without the trailing dot.
+const MAX_NAME_LENGTH: usize = 253;
+
+/// The printable ASCII characters escaped in a path segment.
+const SEGMENT: &[u8] = b" \"#%/<>?\\`{}";
+
+/// Errors which may occur while parsing a name.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum NameError {
+    /// The domain is empty.
+    #[error("the domain is empty")]
+    Empty,
+    /// The domain has a single label.
+    #[error("{:?} is not a domain", name)]
+    NotADomain {
+        /// The name.
+        name: String,
+    },
+    /// The name cannot be converted to punycode, or is not in ASCII and the `idna` feature
+    /// is disabled.
+    #[error("{:?} is not a valid internationalized name", name)]
+    Idna {
+        /// The name.
+        name: String,
+    },
+    /// The name is longer than 253 characters.
+    #[error("{:?} is longer than 253 characters", name)]
+    TooLong {
+        /// The name.
+        name: String,
+    },
+    /// The fully qualified name is not in the zone.
+    #[error("{:?} is not in {}", name, zone)]
+    OutsideZone {
+        /// The name.
+        name: String,
+        /// The zone.
+        zone: String,
+    },
+    /// A label of the name is empty, too long or has characters labels cannot have.
+    #[error("{:?} has an invalid label {:?}", name, label)]
+    InvalidLabel {
+        /// The name.
+        name: String,
+        /// The label, in ASCII.
+        label: String,
+    },
+}
+
+/// Which labels a name may have.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Hostname labels only.
+    Domain,
+    /// Labels such as `_dmarc`, and `*` as the first one.
+    Record,
+}
+
+/// The ASCII form of `name`, without the trailing dot.
+fn to_ascii(name: &str, kind: Kind) -> Result<String, NameError> {
+    let trimmed = name.trim();
+    let trimmed = trimmed.strip_suffix('.').unwrap_or(trimmed);
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+
+    let ascii = if trimmed.is_ascii() {
+        trimmed.to_ascii_lowercase()
+    } else {
+        // The wildcard is not a label IDNA knows about.
+        let (wildcard, rest) = match trimmed.strip_prefix("*.") {
+            Some(rest) if kind == Kind::Record => ("*.", rest),
+            _ => ("", trimmed),
+        };
+        format!("{}{}", wildcard, idna_to_ascii(rest, name)?)
+    };
+
+    if ascii.len() > MAX_NAME_LENGTH {
+        return Err(NameError::TooLong { name: name.into() });
+    }
+    for (index, label) in ascii.split('.').enumerate() {
+        if !is_valid_label(label, kind, index == 0) {
+            return Err(NameError::InvalidLabel {
+                name: name.into(),
+                label: label.into(),
+            });
+        }
+    }
+    Ok(ascii)
+}
+
+fn is_valid_label(label: &str, kind: Kind, first: bool) -> bool {
+    if kind == Kind::Record && first && label == "*" {
+        return true;
+    }
+    !label.is_empty()
+        && label.len() <= MAX_LABEL_LENGTH
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.bytes().all(|byte| {
+            byte.is_ascii_lowercase()
+                || byte.is_ascii_digit()
+                || byte == b'-'
+                || (kind == Kind::Record && byte == b'_')
+        })
+}
+
+#[cfg(feature = "idna")]
+fn idna_to_ascii(unicode: &str, name: &str) -> Result<String, NameError> {
+    idna::domain_to_ascii(unicode).map_err(|_| NameError::Idna { name: name.into() })
+}
+
+#[cfg(not(feature = "idna"))]
+fn idna_to_ascii(_: &str, name: &str) -> Result<String, NameError> {
+    Err(NameError::Idna { name: name.into() })
+}
+
+#[cfg(feature = "idna")]
+fn to_unicode(ascii: &str) -> String {
+    idna::domain_to_unicode(ascii).0
+}
+
+#[cfg(not(feature = "idna"))]
+fn to_unicode(ascii: &str) -> String {
+    ascii.into()
+}
+
+fn is_escaped(byte: u8) -> bool {
+    !byte.is_ascii() || byte.is_ascii_control() || SEGMENT.contains(&byte)
+}
+
+/// Escape `text` to be a single segment of an endpoint path.
+pub(crate) fn path_segment(text: &str) -> Cow<'_, str> {
+    if !text.bytes().any(is_escaped) {
+        return text.into();
+    }
+
+    let mut escaped = String::with_capacity(text.len() * 3);
+    for byte in text.bytes() {
+        if is_escaped(byte) {
+            let _ = write!(escaped, "%{:02X}", byte);
+        } else {
+            escaped.push(byte.into());
+        }
+    }
+    escaped.into()
+}
+
+/// The normalized form of `domain`, or `domain` itself when it is not valid.
+pub(crate) fn normalize_domain(domain: &str) -> Cow<'_, str> {
+    match DomainName::new(domain) {
+        Ok(domain) => domain.0.into(),
+        Err(_) => domain.into(),
+    }
+}
+
+/// The path segment for `domain`: normalized when it is valid, as the endpoint builders
+/// check, and escaped as it is otherwise.
+pub(crate) fn domain_segment(domain: &str) -> String {
+    path_segment(&normalize_domain(domain)).into_owned()
+}
+
+/// The name of `name` relative to `domain`, whichever form it is in, or `name` itself when
+/// either is not valid.
+pub(crate) fn relative_record_name<'a>(name: &'a str, domain: &str) -> Cow<'a, str> {
+    let zone = match DomainName::new(domain) {
+        Ok(zone) => zone,
+        Err(_) => return name.into(),
+    };
+    match RecordName::in_zone(name, &zone) {
+        Ok(name) => name.0.into(),
+        Err(_) => name.into(),
+    }
+}
+
+/// Check the domain and record name given to an endpoint builder, if they were given.
+pub(crate) fn validate_names(domain: Option<&str>, name: Option<&str>) -> Result<(), String> {
+    let domain = match domain {
+        Some(domain) => domain,
+        None => return Ok(()),
+    };
+    let zone = DomainName::new(domain).map_err(|err| err.to_string())?;
+    if let Some(name) = name {
+        RecordName::in_zone(name, &zone).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// The fully qualified form of `name` in `domain`, whichever form it is in.
+///
+/// Names which are not valid are lowercased and appended to the domain as they are.
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) fn record_fqdn(name: &str, domain: &str) -> String {
+    if let Ok(zone) = DomainName::new(domain) {
+        if let Ok(name) = RecordName::in_zone(name, &zone) {
+            return name.to_fqdn(&zone);
+        }
+    }
+    let domain = normalize_domain(domain);
+    match name.trim() {
+        "" | "@" => domain.into_owned(),
+        name => format!("{}.{}", name.to_lowercase(), domain),
+    }
+}
+
+/// A domain, such as `example.com` or `bücher.example`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DomainName(String);
+
+impl DomainName {
+    /// Check and normalize `domain`.
+    pub fn new(domain: &str) -> Result<Self, NameError> {
+        let ascii = to_ascii(domain, Kind::Domain)?;
+        if ascii.is_empty() {
+            return Err(NameError::Empty);
+        }
+        if !ascii.contains('.') {
+            return Err(NameError::NotADomain {
+                name: domain.into(),
+            });
+        }
+        Ok(Self(ascii))
+    }
+
+    /// The ASCII form, as sent to Porkbun.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The Unicode form.
+    pub fn to_unicode(&self) -> String {
+        to_unicode(&self.0)
+    }
+}
+
+impl Display for DomainName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_unicode())
+    }
+}
+
+impl AsRef<str> for DomainName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for DomainName {
+    type Err = NameError;
+
+    fn from_str(domain: &str) -> Result<Self, Self::Err> {
+        Self::new(domain)
+    }
+}
+
+impl TryFrom<String> for DomainName {
+    type Error = NameError;
+
+    fn try_from(domain: String) -> Result<Self, Self::Error> {
+        Self::new(&domain)
+    }
+}
+
+impl From<DomainName> for String {
+    fn from(domain: DomainName) -> Self {
+        domain.0
+    }
+}
+
+impl<'a> From<DomainName> for Cow<'a, str> {
+    fn from(domain: DomainName) -> Self {
+        domain.0.into()
+    }
+}
+
+impl<'a> From<&'a DomainName> for Cow<'a, str> {
+    fn from(domain: &'a DomainName) -> Self {
+        domain.0.as_str().into()
+    }
+}
+
+/// The name of a record relative to its domain, such as `www`, `_dmarc` or `*.dev`.
+///
+/// The empty name is the domain itself.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RecordName(String);
+
+impl RecordName {
+    /// Check and normalize the relative name `name`; `@` is the domain itself.
+    pub fn new(name: &str) -> Result<Self, NameError> {
+        if name.trim() == "@" {
+            return Ok(Self::apex());
+        }
+        to_ascii(name, Kind::Record).map(Self)
+    }
+
+    /// The name of the fully qualified `fqdn` relative to `zone`.
+    pub fn from_fqdn(fqdn: &str, zone: &DomainName) -> Result<Self, NameError> {
+        let ascii = to_ascii(fqdn, Kind::Record)?;
+        if ascii == zone.0 {
+            return Ok(Self::apex());
+        }
+        match ascii.strip_suffix(&zone.0) {
+            Some(name) if name.ends_with('.') => Ok(Self(name.trim_end_matches('.').into())),
+            _ => Err(NameError::OutsideZone {
+                name: fqdn.into(),
+                zone: zone.to_string(),
+            }),
+        }
+    }
+
+    /// The name of `name` relative to `zone`, whichever form it is in.
+    ///
+    /// Names ending with a dot or with the zone are taken as fully qualified, others as
+    /// relative; `@` and the empty name are the zone itself.
+    pub fn in_zone(name: &str, zone: &DomainName) -> Result<Self, NameError> {
+        let trimmed = name.trim();
+        if trimmed.ends_with('.') && trimmed != "." {
+            return Self::from_fqdn(name, zone);
+        }
+        let relative = Self::new(name)?;
+        match Self::from_fqdn(relative.as_str(), zone) {
+            Ok(name) => Ok(name),
+            Err(_) => Ok(relative),
+        }
+    }
+
+    /// The fully qualified form of the name in `zone`, in ASCII and without a trailing dot.
+    pub fn to_fqdn(&self, zone: &DomainName) -> String {
+        if self.is_apex() {
+            zone.0.clone()
+        } else {
+            format!("{}.{}", self.0, zone.0)
+        }
+    }
+
+    /// The name of the domain itself.
+    pub fn apex() -> Self {
+        Self(String::new())
+    }
+
+    /// Whether this is the name of the domain itself.
+    pub fn is_apex(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The ASCII form, as sent to Porkbun.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The Unicode form.
+    pub fn to_unicode(&self) -> String {
+        to_unicode(&self.0)
+    }
+}
+
+impl Display for RecordName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_unicode())
+    }
+}
+
+impl AsRef<str> for RecordName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for RecordName {
+    type Err = NameError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::new(name)
+    }
+}
+
+impl TryFrom<String> for RecordName {
+    type Error = NameError;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::new(&name)
+    }
+}
+
+impl From<RecordName> for String {
+    fn from(name: RecordName) -> Self {
+        name.0
+    }
+}
+
+impl<'a> From<RecordName> for Cow<'a, str> {
+    fn from(name: RecordName) -> Self {
+        name.0.into()
+    }
+}
+
+impl<'a> From<&'a RecordName> for Cow<'a, str> {
+    fn from(name: &'a RecordName) -> Self {
+        name.0.as_str().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "sync", feature = "async"))]
+    use super::record_fqdn;
+    use super::{domain_segment, DomainName, NameError, RecordName};
+
+    #[test]
+    fn domain_normalization() {
+        let domain = DomainName::new(" Example.COM. ").unwrap();
+        assert_eq!(domain.as_str(), "example.com");
+        assert_eq!(domain.to_string(), "example.com");
+    }
+
+    #[cfg(feature = "idna")]
+    #[test]
+    fn internationalized_names() {
+        let domain: DomainName = "Bücher.example".parse().unwrap();
+        assert_eq!(domain.as_str(), "xn--bcher-kva.example");
+        assert_eq!(domain.to_string(), "bücher.example");
+        assert_eq!(DomainName::new("xn--bcher-kva.example").unwrap(), domain);
+
+        let name = RecordName::new("*.bücher").unwrap();
+        assert_eq!(name.as_str(), "*.xn--bcher-kva");
+        assert_eq!(name.to_string(), "*.bücher");
+
+        let www = RecordName::new("www").unwrap();
+        for name in ["WWW.bücher.example", "www.xn--bcher-kva.example."] {
+            assert_eq!(RecordName::in_zone(name, &domain).unwrap(), www, "{}", name);
+        }
+        for name in ["bücher.example", "Bücher.Example."] {
+            assert!(
+                RecordName::in_zone(name, &domain).unwrap().is_apex(),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            RecordName::from_fqdn("_dmarc.xn--bcher-kva.example", &domain).unwrap(),
+            RecordName::new("_dmarc").unwrap()
+        );
+        assert!(matches!(
+            RecordName::from_fqdn("xbücher.example", &domain),
+            Err(NameError::OutsideZone { .. })
+        ));
+        assert_eq!(www.to_fqdn(&domain), "www.xn--bcher-kva.example");
+
+        assert_eq!(domain_segment("bücher.example"), "xn--bcher-kva.example");
+    }
+
+    #[cfg(not(feature = "idna"))]
+    #[test]
+    fn internationalized_names() {
+        assert!(matches!(
+            DomainName::new("bücher.example"),
+            Err(NameError::Idna { .. })
+        ));
+        assert!(matches!(
+            RecordName::new("*.bücher"),
+            Err(NameError::Idna { .. })
+        ));
+        let domain = DomainName::new("xn--bcher-kva.example").unwrap();
+        assert_eq!(domain.to_string(), "xn--bcher-kva.example");
+    }
+
+    #[test]
+    fn invalid_domains() {
+        assert_eq!(DomainName::new(" . "), Err(NameError::Empty));
+        assert!(matches!(
+            DomainName::new("localhost"),
+            Err(NameError::NotADomain { .. })
+        ));
+        for domain in [
+            "example.com/1",
+            "exa mple.com",
+            "example..com",
+            "-example.com",
+            "_dmarc.example.com",
+            "*.example.com",
+        ] {
+            assert!(
+                matches!(DomainName::new(domain), Err(NameError::InvalidLabel { .. })),
+                "{}",
+                domain
+            );
+        }
+        let long = format!("{}.com", "a".repeat(64));
+        assert!(matches!(
+            DomainName::new(&long),
+            Err(NameError::InvalidLabel { .. })
+        ));
+        let long = format!("{}com", "a.".repeat(126));
+        assert!(matches!(
+            DomainName::new(&long),
+            Err(NameError::TooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn record_names() {
+        assert!(RecordName::new("").unwrap().is_apex());
+        assert_eq!(RecordName::new("WWW.").unwrap().as_str(), "www");
+        assert_eq!(RecordName::new("_dmarc").unwrap().as_str(), "_dmarc");
+        assert_eq!(RecordName::new("*.Dev").unwrap().as_str(), "*.dev");
+
+        assert!(RecordName::new("www.*").is_err());
+        assert!(RecordName::new("a b").is_err());
+        assert!(RecordName::new("www/").is_err());
+    }
+
+    #[test]
+    fn zone_forms() {
+        let zone = DomainName::new("example.net").unwrap();
+        let www = RecordName::new("www").unwrap();
+        for name in ["www", "WWW.example.net", "www.example.net."] {
+            assert_eq!(RecordName::in_zone(name, &zone).unwrap(), www, "{}", name);
+        }
+        for name in ["", "@", "example.net", "Example.NET."] {
+            assert!(
+                RecordName::in_zone(name, &zone).unwrap().is_apex(),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            RecordName::in_zone("*.example.net", &zone)
+                .unwrap()
+                .as_str(),
+            "*"
+        );
+        // Relative names may look like other domains.
+        assert_eq!(
+            RecordName::in_zone("www.example.com", &zone)
+                .unwrap()
+                .as_str(),
+            "www.example.com"
+        );
+
+        assert_eq!(
+            RecordName::from_fqdn("_dmarc.example.net", &zone).unwrap(),
+            RecordName::new("_dmarc").unwrap()
+        );
+        assert!(matches!(
+            RecordName::from_fqdn("www.example.com", &zone),
+            Err(NameError::OutsideZone { .. })
+        ));
+        assert!(matches!(
+            RecordName::from_fqdn("xexample.net", &zone),
+            Err(NameError::OutsideZone { .. })
+        ));
+
+        assert_eq!(www.to_fqdn(&zone), "www.example.net");
+        assert_eq!(RecordName::apex().to_fqdn(&zone), "example.net");
+    }
+
+    #[cfg(any(feature = "sync", feature = "async"))]
+    #[test]
+    fn fqdns() {
+        assert_eq!(
+            record_fqdn("WWW.example.com.", "Example.com"),
+            "www.example.com"
+        );
+        assert_eq!(record_fqdn("@", "example.com"), "example.com");
+    }
+
+    #[test]
+    fn serde() {
+        let domain: DomainName = serde_json::from_str("\"Example.com\"").unwrap();
+        assert_eq!(serde_json::to_string(&domain).unwrap(), "\"example.com\"");
+        assert!(serde_json::from_str::<DomainName>("\"example\"").is_err());
+    }
+
+    #[test]
+    fn segments() {
+        assert_eq!(domain_segment("Example.com."), "example.com");
+        assert_eq!(domain_segment("example.com/1"), "example.com%2F1");
+        assert_eq!(domain_segment("a b?"), "a%20b%3F");
+        assert_eq!(domain_segment("ä"), "%C3%A4");
+    }
+}
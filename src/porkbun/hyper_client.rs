@@ -0,0 +1,242 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{Request as HttpRequest, Response as HttpResponse};
+use hyper::{client::HttpConnector, Body};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use log::debug;
+use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore};
+use std::{
+    fmt::{self, Debug},
+    sync::Arc,
+};
+use url::Url;
+
+use super::{response, PorkbunError, PorkbunResult, RestError, API_URL};
+use crate::{
+    api,
+    auth::Auth,
+    middleware::{AsyncMiddleware, AsyncNext, BoxFuture},
+};
+
+/// An asynchronous client for the Porkbun API built on `hyper`.
+///
+/// It behaves like [`AsyncPorkbun`](crate::AsyncPorkbun) and must be used within a tokio
+/// runtime. TLS is always handled by rustls, trusting the Mozilla root certificates bundled
+/// with `webpki-roots`; the `native-tls` and `rustls-tls` features only select the TLS of the
+/// `reqwest` and `ureq` clients.
+///
+/// Request bodies, which carry the credentials, are handed over to `hyper` and are not wiped
+/// once sent.
+#[derive(Clone)]
+pub struct HyperPorkbun {
+    /// The client to use for API calls.
+    client: hyper::Client<HttpsConnector<HttpConnector>>,
+    /// The base URL to use for API calls.
+    url: Url,
+    /// The authentication information to use when communicating with PorkBun.
+    auth: Auth,
+    /// The middleware every request goes through.
+    middleware: Vec<Arc<dyn AsyncMiddleware>>,
+    /// The certificates trusted, the usual roots included.
+    roots: RootCertStore,
+}
+
+fn client(roots: RootCertStore) -> hyper::Client<HttpsConnector<HttpConnector>> {
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = HttpsConnectorBuilder::new()
+        .with_tls_config(config)
+        .https_or_http()
+        .enable_http1()
+        .build();
+    hyper::Client::builder().build(connector)
+}
+
+impl HyperPorkbun {
+    pub fn new(auth: Auth) -> PorkbunResult<Self> {
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        }));
+        let url = Url::parse(API_URL)?;
+
+        Ok(Self {
+            client: client(roots.clone()),
+            url,
+            auth,
+            middleware: Vec::new(),
+            roots,
+        })
+    }
+
+    /// Trust the certificates in `pem`, in PEM format, besides the usual root certificates.
+    ///
+    /// This is for proxies and test servers with their own certificate authority.
+    pub fn with_root_certificate(mut self, pem: &[u8]) -> PorkbunResult<Self> {
+        let certificates =
+            rustls_pemfile::certs(&mut &*pem).map_err(PorkbunError::root_certificate)?;
+        if certificates.is_empty() {
+            return Err(PorkbunError::root_certificate("no certificate found"));
+        }
+        for der in certificates {
+            self.roots
+                .add(&Certificate(der))
+                .map_err(PorkbunError::root_certificate)?;
+        }
+        self.client = client(self.roots.clone());
+        Ok(self)
+    }
+
+    /// Send requests to another API root than `https://porkbun.com/api/json/v3/`.
+    ///
+    /// Endpoints are joined to the URL, so it should end with a `/`.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// Add a middleware at the end of the chain.
+    ///
+    /// Middleware runs in the order it is added: the first one sees the request first and the
+    /// response last.
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: AsyncMiddleware + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    async fn send(&self, request: HttpRequest<Vec<u8>>) -> Result<HttpResponse<Bytes>, RestError> {
+        let rsp = self.client.request(request.map(Body::from)).await?;
+
+        let (parts, body) = rsp.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        Ok(response(parts.status, parts.version, &parts.headers, body)?)
+    }
+}
+
+impl Debug for HyperPorkbun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HyperPorkbun")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl api::AsyncClient for HyperPorkbun {
+    type Error = RestError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, api::ApiError<Self::Error>> {
+        debug!(target: "porkbun", "REST api call {}", endpoint);
+        Ok(self.url.join(endpoint)?)
+    }
+
+    async fn rest_async(
+        &self,
+        request: http::request::Builder,
+        body: Vec<u8>,
+    ) -> Result<HttpResponse<Bytes>, api::ApiError<Self::Error>> {
+        let call = async {
+            let http_request = request.body(body)?;
+            let send = |request| -> BoxFuture<'_, _> { Box::pin(self.send(request)) };
+            AsyncNext::new(&self.middleware, &send)
+                .run(http_request)
+                .await
+        };
+
+        call.await.map_err(api::ApiError::client)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(self.auth.clone())
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use crate::{
+        api::{self, ApiError, AsyncQuery},
+        auth::Auth,
+        endpoints::{DeleteDns, Ping},
+        test::FakePorkbun,
+        PingResponse,
+    };
+
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    use crate::test::tls::TestCertificates;
+    use crate::PorkbunError;
+
+    use super::HyperPorkbun;
+
+    #[tokio::test]
+    async fn fake_server() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_your_ip("192.0.2.1");
+        let server = fake.serve().unwrap();
+        let client = HyperPorkbun::new(Auth::new("pk1".into(), "sk1".into()))
+            .unwrap()
+            .with_url(server.url());
+
+        let rsp: PingResponse = Ping::builder()
+            .build()
+            .unwrap()
+            .query_async(&client)
+            .await
+            .unwrap();
+        assert_eq!(rsp.your_ip, "192.0.2.1");
+
+        let delete = DeleteDns::builder()
+            .domain("example.com")
+            .id("1")
+            .build()
+            .unwrap();
+        let err = api::ignore(delete).query_async(&client).await.unwrap_err();
+        if let ApiError::PorkBun { message, .. } = err {
+            assert_eq!(message, "Delete error: Invalid record id.");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    #[tokio::test]
+    async fn root_certificate() {
+        let certificates = TestCertificates::new();
+        let server = FakePorkbun::new()
+            .serve_tls(
+                certificates.certificate.as_bytes(),
+                certificates.key.as_bytes(),
+            )
+            .unwrap();
+
+        let ping = Ping::builder().build().unwrap();
+        let untrusted = HyperPorkbun::new(Auth::new("pk1".into(), "sk1".into()))
+            .unwrap()
+            .with_url(server.url());
+        let rsp: Result<PingResponse, _> = ping.query_async(&untrusted).await;
+        assert!(rsp.is_err());
+
+        let client = untrusted
+            .with_root_certificate(certificates.ca.as_bytes())
+            .unwrap();
+        let _: PingResponse = ping.query_async(&client).await.unwrap();
+    }
+
+    #[test]
+    fn invalid_root_certificate() {
+        let client = HyperPorkbun::new(Auth::new("pk1".into(), "sk1".into())).unwrap();
+        let err = client
+            .with_root_certificate(b"not a certificate")
+            .unwrap_err();
+        assert!(matches!(err, PorkbunError::RootCertificate { .. }));
+    }
+}
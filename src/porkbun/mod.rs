@@ -0,0 +1,454 @@
+#[cfg(feature = "client_api")]
+use crate::{
+    api,
+    auth::Auth,
+    middleware::{AsyncMiddleware, AsyncNext, BoxFuture, Middleware, Next},
+};
+#[cfg(feature = "client_api")]
+use async_trait::async_trait;
+use bytes::Bytes;
+#[cfg(feature = "client_api")]
+use http::Request as HttpRequest;
+use http::{
+    header::{HeaderName, HeaderValue},
+    Response as HttpResponse, StatusCode, Version,
+};
+#[cfg(feature = "client_api")]
+use log::debug;
+#[cfg(feature = "client_api")]
+use reqwest::{blocking::Client, Client as AsyncClient};
+use std::{convert::TryFrom, error::Error as StdError};
+#[cfg(feature = "client_api")]
+use std::{
+    convert::TryInto,
+    fmt::{self, Debug},
+    sync::Arc,
+};
+use thiserror::Error;
+#[cfg(feature = "client_api")]
+use url::Url;
+
+#[cfg(feature = "hyper-client")]
+mod hyper_client;
+#[cfg(feature = "ureq-client")]
+mod ureq_client;
+
+#[cfg(feature = "hyper-client")]
+pub use self::hyper_client::HyperPorkbun;
+#[cfg(feature = "ureq-client")]
+pub use self::ureq_client::UreqPorkbun;
+
+#[cfg(all(
+    any(feature = "client_api", feature = "ureq-client"),
+    not(any(feature = "native-tls", feature = "rustls-tls"))
+))]
+compile_error!(
+    "`client_api` and `ureq-client`, and the `cli`, `ddns` and `acme-hooks` features built on \
+     them, need a TLS backend: enable `native-tls` or `rustls-tls`"
+);
+
+/// The API root of Porkbun.
+const API_URL: &str = "https://porkbun.com/api/json/v3/";
+
+#[derive(Debug, Error)]
+pub enum PorkbunError {
+    #[error("failed to parse url: {}", source)]
+    UrlParse {
+        #[from]
+        source: url::ParseError,
+    },
+    #[cfg(feature = "client_api")]
+    #[error("failed to set up the HTTP client: {}", source)]
+    Client {
+        #[from]
+        source: reqwest::Error,
+    },
+    #[cfg(all(feature = "ureq-client", feature = "native-tls"))]
+    #[error("failed to set up TLS: {}", source)]
+    NativeTls {
+        #[from]
+        source: native_tls::Error,
+    },
+    #[cfg(feature = "hyper-client")]
+    #[error("invalid root certificate: {}", source)]
+    RootCertificate {
+        source: Box<dyn StdError + Send + Sync>,
+    },
+}
+
+type PorkbunResult<T> = Result<T, PorkbunError>;
+
+#[cfg(feature = "hyper-client")]
+impl PorkbunError {
+    fn root_certificate<E>(source: E) -> Self
+    where
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        PorkbunError::RootCertificate {
+            source: source.into(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RestError {
+    #[cfg(feature = "client_api")]
+    #[error("communication with porkbun: {}", source)]
+    Communication {
+        #[from]
+        source: reqwest::Error,
+    },
+    #[cfg(feature = "ureq-client")]
+    #[error("communication with porkbun: {}", source)]
+    Ureq { source: Box<ureq::Error> },
+    #[cfg(feature = "hyper-client")]
+    #[error("communication with porkbun: {}", source)]
+    Hyper {
+        #[from]
+        source: hyper::Error,
+    },
+    #[error("failed to read the response: {}", source)]
+    Io {
+        #[from]
+        source: std::io::Error,
+    },
+    #[error("`http` error: {}", source)]
+    Http {
+        #[from]
+        source: http::Error,
+    },
+    #[error("middleware error: {}", source)]
+    Middleware {
+        source: Box<dyn StdError + Send + Sync>,
+    },
+}
+
+impl RestError {
+    /// Create an error raised by a middleware.
+    pub fn middleware<E>(source: E) -> Self
+    where
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        RestError::Middleware {
+            source: source.into(),
+        }
+    }
+}
+
+/// Collect the parts of a response received by any backend into the response endpoints decode.
+fn response<S, I, K, V>(
+    status: S,
+    version: Version,
+    headers: I,
+    body: Bytes,
+) -> Result<HttpResponse<Bytes>, http::Error>
+where
+    StatusCode: TryFrom<S>,
+    <StatusCode as TryFrom<S>>::Error: Into<http::Error>,
+    I: IntoIterator<Item = (K, V)>,
+    HeaderName: TryFrom<K>,
+    <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+    HeaderValue: TryFrom<V>,
+    <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+{
+    let mut rsp = HttpResponse::builder().status(status).version(version);
+    for (key, value) in headers {
+        rsp = rsp.header(key, value);
+    }
+    rsp.body(body)
+}
+
+#[cfg(feature = "client_api")]
+/// A representation of the Porkbun API for a single user.
+///
+/// Separate users should use separate instances of this.
+///
+/// Request bodies, which carry the credentials, are handed over to `reqwest` and are not wiped
+/// once sent; use `UreqPorkbun` where that matters.
+#[derive(Clone)]
+pub struct Porkbun {
+    /// The client to use for API calls.
+    client: Client,
+    /// The base URL to use for API calls.
+    url: Url,
+    /// The authentication information to use when communicating with PorkBun.
+    auth: Auth,
+    /// The middleware every request goes through.
+    middleware: Vec<Arc<dyn Middleware>>,
+    /// The certificates trusted besides the usual roots.
+    root_certificates: Vec<reqwest::Certificate>,
+}
+
+#[cfg(feature = "client_api")]
+impl Porkbun {
+    pub fn new(auth: Auth) -> PorkbunResult<Self> {
+        let client = Client::new();
+        let url = Url::parse(API_URL)?;
+
+        Ok(Self {
+            client,
+            url,
+            auth,
+            middleware: Vec::new(),
+            root_certificates: Vec::new(),
+        })
+    }
+
+    /// Send requests to another API root than `https://porkbun.com/api/json/v3/`.
+    ///
+    /// Endpoints are joined to the URL, so it should end with a `/`.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// Trust `pem`, a certificate in PEM format, besides the usual root certificates.
+    ///
+    /// This is for proxies and test servers with their own certificate authority.
+    pub fn with_root_certificate(mut self, pem: &[u8]) -> PorkbunResult<Self> {
+        self.root_certificates
+            .push(reqwest::Certificate::from_pem(pem)?);
+        let builder = self
+            .root_certificates
+            .iter()
+            .cloned()
+            .fold(Client::builder(), |builder, certificate| {
+                builder.add_root_certificate(certificate)
+            });
+        self.client = builder.build()?;
+        Ok(self)
+    }
+
+    /// Add a middleware at the end of the chain.
+    ///
+    /// Middleware runs in the order it is added: the first one sees the request first and the
+    /// response last.
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    fn send(&self, request: HttpRequest<Vec<u8>>) -> Result<HttpResponse<Bytes>, RestError> {
+        let request = request.try_into()?;
+        let rsp = self.client.execute(request)?;
+
+        let (status, version, headers) = (rsp.status(), rsp.version(), rsp.headers().clone());
+        Ok(response(status, version, &headers, rsp.bytes()?)?)
+    }
+}
+
+#[cfg(feature = "client_api")]
+impl Debug for Porkbun {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Porkbun").field("url", &self.url).finish()
+    }
+}
+
+#[cfg(feature = "client_api")]
+impl api::Client for Porkbun {
+    type Error = RestError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, api::ApiError<Self::Error>> {
+        debug!(target: "porkbun", "REST api call {}", endpoint);
+        Ok(self.url.join(endpoint)?)
+    }
+
+    fn rest(
+        &self,
+        request: http::request::Builder,
+        body: Vec<u8>,
+    ) -> Result<HttpResponse<Bytes>, api::ApiError<Self::Error>> {
+        let call = || -> Result<_, RestError> {
+            let http_request = request.body(body)?;
+            let send = |request| self.send(request);
+            Next::new(&self.middleware, &send).run(http_request)
+        };
+        call().map_err(api::ApiError::client)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(self.auth.clone())
+    }
+}
+
+/// An asynchronous representation of the Porkbun API for a single user.
+///
+/// Like [`Porkbun`], it hands request bodies over to `reqwest` without wiping them.
+#[cfg(feature = "client_api")]
+#[derive(Clone)]
+pub struct AsyncPorkbun {
+    /// The client to use for API calls.
+    client: reqwest::Client,
+    /// The base URL to use for API calls.
+    url: Url,
+    /// The authentication information to use when communicating with PorkBun.
+    auth: Auth,
+    /// The middleware every request goes through.
+    middleware: Vec<Arc<dyn AsyncMiddleware>>,
+    /// The certificates trusted besides the usual roots.
+    root_certificates: Vec<reqwest::Certificate>,
+}
+
+#[cfg(feature = "client_api")]
+impl Debug for AsyncPorkbun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncPorkbun")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+#[cfg(feature = "client_api")]
+#[async_trait]
+impl api::AsyncClient for AsyncPorkbun {
+    type Error = RestError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, api::ApiError<Self::Error>> {
+        debug!(target: "porkbun", "REST api call {}", endpoint);
+        Ok(self.url.join(endpoint)?)
+    }
+
+    async fn rest_async(
+        &self,
+        request: http::request::Builder,
+        body: Vec<u8>,
+    ) -> Result<HttpResponse<Bytes>, api::ApiError<Self::Error>> {
+        use futures_util::TryFutureExt;
+        let call = || async {
+            let http_request = request.body(body)?;
+            let send = |request| -> BoxFuture<'_, _> { Box::pin(self.send(request)) };
+            AsyncNext::new(&self.middleware, &send)
+                .run(http_request)
+                .await
+        };
+
+        call().map_err(api::ApiError::client).await
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(self.auth.clone())
+    }
+}
+
+#[cfg(feature = "client_api")]
+impl AsyncPorkbun {
+    pub fn new(auth: Auth) -> PorkbunResult<Self> {
+        let client = AsyncClient::new();
+        let url = Url::parse(API_URL)?;
+
+        Ok(Self {
+            client,
+            url,
+            auth,
+            middleware: Vec::new(),
+            root_certificates: Vec::new(),
+        })
+    }
+
+    /// Send requests to another API root than `https://porkbun.com/api/json/v3/`.
+    ///
+    /// Endpoints are joined to the URL, so it should end with a `/`.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// Trust `pem`, a certificate in PEM format, besides the usual root certificates.
+    ///
+    /// This is for proxies and test servers with their own certificate authority.
+    pub fn with_root_certificate(mut self, pem: &[u8]) -> PorkbunResult<Self> {
+        self.root_certificates
+            .push(reqwest::Certificate::from_pem(pem)?);
+        let builder = self
+            .root_certificates
+            .iter()
+            .cloned()
+            .fold(AsyncClient::builder(), |builder, certificate| {
+                builder.add_root_certificate(certificate)
+            });
+        self.client = builder.build()?;
+        Ok(self)
+    }
+
+    /// Add a middleware at the end of the chain.
+    ///
+    /// Middleware runs in the order it is added: the first one sees the request first and the
+    /// response last.
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: AsyncMiddleware + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    async fn send(&self, request: HttpRequest<Vec<u8>>) -> Result<HttpResponse<Bytes>, RestError> {
+        let request = request.try_into()?;
+        let rsp = self.client.execute(request).await?;
+
+        let (status, version, headers) = (rsp.status(), rsp.version(), rsp.headers().clone());
+        Ok(response(status, version, &headers, rsp.bytes().await?)?)
+    }
+}
+
+#[cfg(all(test, feature = "client_api"))]
+mod tests {
+    use crate::{
+        api::{AsyncQuery, Query},
+        auth::Auth,
+        endpoints::Ping,
+        test::{tls::TestCertificates, FakePorkbun},
+        PingResponse,
+    };
+
+    use super::{AsyncPorkbun, Porkbun};
+
+    fn auth() -> Auth {
+        Auth::new("pk1".into(), "sk1".into())
+    }
+
+    #[test]
+    fn root_certificate() {
+        let certificates = TestCertificates::new();
+        let server = FakePorkbun::new()
+            .serve_tls(
+                certificates.certificate.as_bytes(),
+                certificates.key.as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(server.url().scheme(), "https");
+
+        let ping = Ping::builder().build().unwrap();
+        let untrusted = Porkbun::new(auth()).unwrap().with_url(server.url());
+        assert!(Query::<PingResponse, _>::query(&ping, &untrusted).is_err());
+
+        let client = untrusted
+            .with_root_certificate(certificates.ca.as_bytes())
+            .unwrap();
+        let _: PingResponse = ping.query(&client).unwrap();
+    }
+
+    #[tokio::test]
+    async fn root_certificate_async() {
+        let certificates = TestCertificates::new();
+        let server = FakePorkbun::new()
+            .serve_tls(
+                certificates.certificate.as_bytes(),
+                certificates.key.as_bytes(),
+            )
+            .unwrap();
+
+        let ping = Ping::builder().build().unwrap();
+        let untrusted = AsyncPorkbun::new(auth()).unwrap().with_url(server.url());
+        let rsp: Result<PingResponse, _> = ping.query_async(&untrusted).await;
+        assert!(rsp.is_err());
+
+        let client = untrusted
+            .with_root_certificate(certificates.ca.as_bytes())
+            .unwrap();
+        let _: PingResponse = ping.query_async(&client).await.unwrap();
+    }
+}
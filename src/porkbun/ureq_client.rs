@@ -0,0 +1,257 @@
+use bytes::Bytes;
+use http::{Request as HttpRequest, Response as HttpResponse, Version};
+use log::debug;
+use std::{
+    fmt::{self, Debug},
+    io::Read,
+    sync::Arc,
+};
+use url::Url;
+use zeroize::Zeroize;
+
+use super::{response, PorkbunResult, RestError, API_URL};
+use crate::{
+    api,
+    auth::Auth,
+    middleware::{Middleware, Next},
+};
+
+/// A blocking client for the Porkbun API built on `ureq`.
+///
+/// It behaves like [`Porkbun`](crate::Porkbun) without pulling in an asynchronous runtime.
+#[derive(Clone)]
+pub struct UreqPorkbun {
+    /// The agent to use for API calls.
+    agent: ureq::Agent,
+    /// The base URL to use for API calls.
+    url: Url,
+    /// The authentication information to use when communicating with PorkBun.
+    auth: Auth,
+    /// The middleware every request goes through.
+    middleware: Vec<Arc<dyn Middleware>>,
+    /// The certificates trusted besides the usual roots.
+    #[cfg(feature = "native-tls")]
+    root_certificates: Vec<native_tls::Certificate>,
+}
+
+impl UreqPorkbun {
+    pub fn new(auth: Auth) -> PorkbunResult<Self> {
+        let agent = ureq::AgentBuilder::new();
+        // Like `reqwest`, prefer the platform's TLS when both backends are enabled.
+        #[cfg(feature = "native-tls")]
+        let agent = agent.tls_connector(Arc::new(native_tls::TlsConnector::new()?));
+        let agent = agent.build();
+        let url = Url::parse(API_URL)?;
+
+        Ok(Self {
+            agent,
+            url,
+            auth,
+            middleware: Vec::new(),
+            #[cfg(feature = "native-tls")]
+            root_certificates: Vec::new(),
+        })
+    }
+
+    /// Send requests through `agent`, for instance to set timeouts or a proxy.
+    pub fn with_agent(mut self, agent: ureq::Agent) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    /// Trust `pem`, a certificate in PEM format, besides the usual root certificates.
+    ///
+    /// This is for proxies and test servers with their own certificate authority. It replaces
+    /// the agent given to [`UreqPorkbun::with_agent`], if any.
+    ///
+    /// This needs the `native-tls` feature. With `rustls-tls` alone, `ureq` takes a rustls
+    /// configuration from a rustls version this crate does not otherwise depend on: build an
+    /// agent with `ureq::AgentBuilder::tls_config` and pass it to [`UreqPorkbun::with_agent`]
+    /// instead.
+    #[cfg(feature = "native-tls")]
+    pub fn with_root_certificate(mut self, pem: &[u8]) -> PorkbunResult<Self> {
+        self.root_certificates
+            .push(native_tls::Certificate::from_pem(pem)?);
+        let mut connector = native_tls::TlsConnector::builder();
+        for certificate in &self.root_certificates {
+            connector.add_root_certificate(certificate.clone());
+        }
+        self.agent = ureq::AgentBuilder::new()
+            .tls_connector(Arc::new(connector.build()?))
+            .build();
+        Ok(self)
+    }
+
+    /// Send requests to another API root than `https://porkbun.com/api/json/v3/`.
+    ///
+    /// Endpoints are joined to the URL, so it should end with a `/`.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// Add a middleware at the end of the chain.
+    ///
+    /// Middleware runs in the order it is added: the first one sees the request first and the
+    /// response last.
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    fn send(&self, request: HttpRequest<Vec<u8>>) -> Result<HttpResponse<Bytes>, RestError> {
+        let (parts, mut body) = request.into_parts();
+        let mut request = self
+            .agent
+            .request(parts.method.as_str(), &parts.uri.to_string());
+        for (key, value) in &parts.headers {
+            request = request.set(key.as_str(), &String::from_utf8_lossy(value.as_bytes()));
+        }
+
+        // Porkbun reports errors with 4xx statuses and a JSON body, which endpoints decode.
+        let sent = request.send_bytes(&body);
+        // The body carries the credentials and ureq only borrowed it.
+        body.zeroize();
+        let rsp = match sent {
+            Ok(rsp) | Err(ureq::Error::Status(_, rsp)) => rsp,
+            Err(err) => {
+                return Err(RestError::Ureq {
+                    source: Box::new(err),
+                })
+            }
+        };
+
+        let version = match rsp.http_version() {
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/2.0" => Version::HTTP_2,
+            _ => Version::HTTP_11,
+        };
+        let headers = rsp
+            .headers_names()
+            .into_iter()
+            .flat_map(|name| {
+                rsp.all(&name)
+                    .into_iter()
+                    .map(|value| (name.clone(), value.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let status = rsp.status();
+        let mut body = Vec::new();
+        rsp.into_reader().read_to_end(&mut body)?;
+
+        Ok(response(status, version, headers, body.into())?)
+    }
+}
+
+impl Debug for UreqPorkbun {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UreqPorkbun")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl api::Client for UreqPorkbun {
+    type Error = RestError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, api::ApiError<Self::Error>> {
+        debug!(target: "porkbun", "REST api call {}", endpoint);
+        Ok(self.url.join(endpoint)?)
+    }
+
+    fn rest(
+        &self,
+        request: http::request::Builder,
+        body: Vec<u8>,
+    ) -> Result<HttpResponse<Bytes>, api::ApiError<Self::Error>> {
+        let call = || -> Result<_, RestError> {
+            let http_request = request.body(body)?;
+            let send = |request| self.send(request);
+            Next::new(&self.middleware, &send).run(http_request)
+        };
+        call().map_err(api::ApiError::client)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(self.auth.clone())
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use crate::{
+        api::{self, ApiError, Query},
+        auth::Auth,
+        endpoints::{DeleteDns, Ping},
+        test::FakePorkbun,
+        PingResponse,
+    };
+
+    #[cfg(feature = "native-tls")]
+    use crate::{test::tls::TestCertificates, PorkbunError};
+
+    use super::UreqPorkbun;
+
+    #[test]
+    fn fake_server() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_your_ip("192.0.2.1");
+        let server = fake.serve().unwrap();
+        let client = UreqPorkbun::new(Auth::new("pk1".into(), "sk1".into()))
+            .unwrap()
+            .with_url(server.url());
+
+        let rsp: PingResponse = Ping::builder().build().unwrap().query(&client).unwrap();
+        assert_eq!(rsp.your_ip, "192.0.2.1");
+
+        let delete = DeleteDns::builder()
+            .domain("example.com")
+            .id("1")
+            .build()
+            .unwrap();
+        let err = api::ignore(delete).query(&client).unwrap_err();
+        if let ApiError::PorkBun { message, .. } = err {
+            assert_eq!(message, "Delete error: Invalid record id.");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[test]
+    fn root_certificate() {
+        let certificates = TestCertificates::new();
+        let server = FakePorkbun::new()
+            .serve_tls(
+                certificates.certificate.as_bytes(),
+                certificates.key.as_bytes(),
+            )
+            .unwrap();
+
+        let ping = Ping::builder().build().unwrap();
+        let untrusted = UreqPorkbun::new(Auth::new("pk1".into(), "sk1".into()))
+            .unwrap()
+            .with_url(server.url());
+        assert!(Query::<PingResponse, _>::query(&ping, &untrusted).is_err());
+
+        let client = untrusted
+            .with_root_certificate(certificates.ca.as_bytes())
+            .unwrap();
+        let _: PingResponse = ping.query(&client).unwrap();
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[test]
+    fn invalid_root_certificate() {
+        let client = UreqPorkbun::new(Auth::new("pk1".into(), "sk1".into())).unwrap();
+        let err = client
+            .with_root_certificate(b"not a certificate")
+            .unwrap_err();
+        assert!(matches!(err, PorkbunError::NativeTls { .. }));
+    }
+}
@@ -0,0 +1,626 @@
+//! Bring the records of a domain in line with a desired state.
+//!
+//! A [`DesiredZone`] lists the records a domain should have. Compared with the records
+//! Porkbun has, it gives a [`Plan`] of the changes to make, which can be reviewed, printed or
+//! serialized before being applied:
+//!
+//! - records which are already as desired are left alone;
+//! - records with the same name and type as a desired one but other content or TTL are edited
+//!   in place;
+//! - desired records left over are created, and records left over are deleted.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+use thiserror::Error;
+
+use crate::{
+    api::ApiError,
+    endpoints::{
+        CreateDns, CreateDnsBuilderError, DnsContent, DnsContentError, EditDns, EditDnsBuilderError,
+    },
+    name::{normalize_domain, record_fqdn, relative_record_name},
+    Record,
+};
+#[cfg(feature = "sync")]
+use crate::{
+    api::{Client, Query},
+    endpoints::{DeleteDns, RetrieveDns},
+    CreateResponse, DeleteResponse, EditResponse, RetrieveResponse,
+};
+
+/// A record a domain should have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesiredRecord {
+    /// The subdomain, without the domain; the domain itself if `None`.
+    ///
+    /// Fully qualified names and `@` are accepted as well.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The type and content of the record.
+    #[serde(flatten)]
+    pub record: DnsContent,
+    /// The TTL, in seconds; any TTL will do if `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+}
+
+impl DesiredRecord {
+    /// A record in the domain itself, with any TTL.
+    pub fn new(record: DnsContent) -> Self {
+        Self {
+            name: None,
+            record,
+            ttl: None,
+        }
+    }
+
+    /// Put the record in a subdomain.
+    pub fn name<S>(mut self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Require a TTL.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The record of `domain` that Porkbun reported, with its TTL.
+    pub fn from_record(domain: &str, record: &Record) -> Result<Self, DnsContentError> {
+        let priority = record
+            .priority
+            .as_deref()
+            .and_then(|prio| prio.parse().ok());
+        let name = relative_record_name(&record.name, domain);
+        let name = if name.is_empty() {
+            None
+        } else {
+            Some(name.to_lowercase())
+        };
+
+        Ok(Self {
+            name,
+            record: DnsContent::new(&record.record_type, &record.content, priority)?,
+            ttl: record.ttl.parse().ok(),
+        })
+    }
+
+    /// The endpoint creating this record in `domain`, unless either name is not valid.
+    pub fn create<'a>(&'a self, domain: &'a str) -> Result<CreateDns<'a>, CreateDnsBuilderError> {
+        let mut endpoint = CreateDns::builder();
+        endpoint.domain(domain).record(self.record.clone());
+        if let Some(name) = &self.name {
+            endpoint.name(name.as_str());
+        }
+        if let Some(ttl) = self.ttl {
+            endpoint.ttl(ttl);
+        }
+        endpoint.build()
+    }
+
+    /// The endpoint replacing the record `id` of `domain` with this one, unless either name is
+    /// not valid.
+    pub fn edit<'a>(
+        &'a self,
+        domain: &'a str,
+        id: &'a str,
+    ) -> Result<EditDns<'a>, EditDnsBuilderError> {
+        let mut endpoint = EditDns::builder();
+        endpoint.domain(domain).id(id).record(self.record.clone());
+        if let Some(name) = &self.name {
+            endpoint.name(name.as_str());
+        }
+        if let Some(ttl) = self.ttl {
+            endpoint.ttl(ttl);
+        }
+        endpoint.build()
+    }
+
+    pub(crate) fn fqdn(&self, domain: &str) -> String {
+        record_fqdn(self.name.as_deref().unwrap_or_default(), domain)
+    }
+
+    /// Whether `record` has this content, ignoring the case and trailing dot of names.
+    pub(crate) fn same_content(&self, record: &Record) -> bool {
+        let priority = record
+            .priority
+            .as_deref()
+            .and_then(|prio| prio.parse().ok())
+            .unwrap_or(0);
+        if self.record.priority().is_some_and(|prio| prio != priority) {
+            return false;
+        }
+
+        let (desired, current) = (self.record.content(), record.content.as_str());
+        match self.record {
+            DnsContent::A { content } => current.parse().ok() == Some(content),
+            DnsContent::Aaaa { content } => current.parse().ok() == Some(content),
+            DnsContent::Ns { .. }
+            | DnsContent::Cname { .. }
+            | DnsContent::Mx { .. }
+            | DnsContent::Srv { .. } => desired
+                .trim_end_matches('.')
+                .eq_ignore_ascii_case(current.trim_end_matches('.')),
+            DnsContent::Txt { .. } | DnsContent::Caa { .. } | DnsContent::Tlsa { .. } => {
+                desired == current
+            }
+        }
+    }
+
+    fn same_ttl(&self, record: &Record) -> bool {
+        self.ttl.is_none_or(|ttl| record.ttl.parse() == Ok(ttl))
+    }
+}
+
+impl From<CreateDns<'_>> for DesiredRecord {
+    fn from(endpoint: CreateDns<'_>) -> Self {
+        Self {
+            name: endpoint.name.map(Into::into),
+            record: endpoint.record,
+            ttl: endpoint.ttl,
+        }
+    }
+}
+
+/// A change to the records of a domain.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Change {
+    /// Delete a record.
+    Delete {
+        /// The record.
+        current: Record,
+    },
+    /// Edit a record in place.
+    Edit {
+        /// The record as it is.
+        current: Record,
+        /// The record as it should be.
+        desired: DesiredRecord,
+    },
+    /// Create a record.
+    Create {
+        /// The record as it should be.
+        desired: DesiredRecord,
+    },
+}
+
+/// The changes bringing a domain in line with its desired state.
+///
+/// Deletions come first, then edits, then creations, so that records being replaced never
+/// conflict with the new ones.
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan {
+    /// The domain.
+    pub domain: String,
+    /// The changes, in the order they are applied.
+    pub changes: Vec<Change>,
+}
+
+/// Errors which may occur while applying a plan.
+#[derive(Debug, Error)]
+#[error("failed to apply change {} of the plan: {}", applied + 1, source)]
+pub struct ApplyError<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// How many changes were applied before the failure.
+    pub applied: usize,
+    /// The source of the error.
+    #[source]
+    pub source: ApiError<E>,
+}
+
+impl Plan {
+    /// Whether the domain is already as desired.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Make the changes, stopping at the first failure.
+    #[cfg(feature = "sync")]
+    pub fn apply<C>(&self, client: &C) -> Result<(), ApplyError<C::Error>>
+    where
+        C: Client,
+    {
+        for (applied, change) in self.changes.iter().enumerate() {
+            self.apply_change(client, change)
+                .map_err(|source| ApplyError { applied, source })?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "sync")]
+    fn apply_change<C>(&self, client: &C, change: &Change) -> Result<(), ApiError<C::Error>>
+    where
+        C: Client,
+    {
+        let domain = self.domain.as_str();
+        match change {
+            Change::Delete { current } => {
+                let _: DeleteResponse = DeleteDns::builder()
+                    .domain(domain)
+                    .id(current.id.as_str())
+                    .build()
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+            }
+            Change::Edit { current, desired } => {
+                let _: EditResponse = desired
+                    .edit(domain, &current.id)
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+            }
+            Change::Create { desired } => {
+                let _: CreateResponse = desired
+                    .create(domain)
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn describe_current(record: &Record) -> String {
+    let priority = match record.record_type.as_str() {
+        "MX" | "SRV" => format!("{} ", record.priority.as_deref().unwrap_or("0")),
+        _ => String::new(),
+    };
+    format!(
+        "{} {} {} {}{}",
+        record.name, record.ttl, record.record_type, priority, record.content
+    )
+}
+
+fn describe_desired(domain: &str, desired: &DesiredRecord) -> String {
+    let ttl = desired
+        .ttl
+        .map_or_else(|| "-".into(), |ttl| ttl.to_string());
+    let priority = desired
+        .record
+        .priority()
+        .map_or_else(String::new, |prio| format!("{} ", prio));
+    format!(
+        "{} {} {} {}{}",
+        desired.fqdn(domain),
+        ttl,
+        desired.record.record_type(),
+        priority,
+        desired.record.content()
+    )
+}
+
+impl Display for Plan {
+    /// One line per change: `-` for deletions, `~` for edits and `+` for creations.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            match change {
+                Change::Delete { current } => writeln!(f, "- {}", describe_current(current))?,
+                Change::Edit { current, desired } => writeln!(
+                    f,
+                    "~ {} (was {})",
+                    describe_desired(&self.domain, desired),
+                    describe_current(current),
+                )?,
+                Change::Create { desired } => {
+                    writeln!(f, "+ {}", describe_desired(&self.domain, desired))?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The records a domain should have.
+#[derive(Debug, Clone)]
+pub struct DesiredZone {
+    domain: String,
+    records: Vec<DesiredRecord>,
+    keep_unmanaged: bool,
+}
+
+impl DesiredZone {
+    /// `domain` should have exactly `records`.
+    pub fn new<S, I, R>(domain: S, records: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = R>,
+        R: Into<DesiredRecord>,
+    {
+        Self {
+            domain: normalize_domain(&domain.into().trim_end_matches('.').to_lowercase())
+                .into_owned(),
+            records: records.into_iter().map(Into::into).collect(),
+            keep_unmanaged: false,
+        }
+    }
+
+    /// Leave alone the records whose name and type no desired record has, instead of deleting
+    /// them.
+    pub fn keep_unmanaged(mut self) -> Self {
+        self.keep_unmanaged = true;
+        self
+    }
+
+    /// Compare with the records the domain has.
+    ///
+    /// The NS records of the domain itself are Porkbun's nameservers and are only managed if
+    /// some are desired.
+    pub fn diff(&self, current: &[Record]) -> Plan {
+        type Group<'a> = (Vec<&'a DesiredRecord>, Vec<&'a Record>);
+        let mut groups: BTreeMap<(String, String), Group<'_>> = BTreeMap::new();
+        for desired in &self.records {
+            let key = (
+                desired.fqdn(&self.domain),
+                desired.record.record_type().to_string(),
+            );
+            groups.entry(key).or_default().0.push(desired);
+        }
+        for record in current {
+            let key = (
+                record_fqdn(&record.name, &self.domain),
+                record.record_type.to_uppercase(),
+            );
+            groups.entry(key).or_default().1.push(record);
+        }
+
+        let (mut deletes, mut edits, mut creates) = (Vec::new(), Vec::new(), Vec::new());
+        for ((name, record_type), (desired, mut current)) in groups {
+            if desired.is_empty() {
+                let porkbun_ns = name == self.domain && record_type == "NS";
+                if !self.keep_unmanaged && !porkbun_ns {
+                    deletes.extend(current.into_iter().map(|current| Change::Delete {
+                        current: current.clone(),
+                    }));
+                }
+                continue;
+            }
+
+            // Records with the desired content first, at most edited for their TTL.
+            let mut unmatched = Vec::new();
+            for desired in desired {
+                match current
+                    .iter()
+                    .position(|record| desired.same_content(record))
+                {
+                    Some(index) => {
+                        let record = current.remove(index);
+                        if !desired.same_ttl(record) {
+                            edits.push(Change::Edit {
+                                current: record.clone(),
+                                desired: desired.clone(),
+                            });
+                        }
+                    }
+                    None => unmatched.push(desired),
+                }
+            }
+
+            // Then the others in turn, edited in place while there are records left.
+            let mut current = current.into_iter();
+            for desired in unmatched {
+                match current.next() {
+                    Some(record) => edits.push(Change::Edit {
+                        current: record.clone(),
+                        desired: desired.clone(),
+                    }),
+                    None => creates.push(Change::Create {
+                        desired: desired.clone(),
+                    }),
+                }
+            }
+            deletes.extend(current.map(|current| Change::Delete {
+                current: current.clone(),
+            }));
+        }
+
+        Plan {
+            domain: self.domain.clone(),
+            changes: deletes.into_iter().chain(edits).chain(creates).collect(),
+        }
+    }
+
+    /// Compare with the records the domain has on Porkbun.
+    #[cfg(feature = "sync")]
+    pub fn plan<C>(&self, client: &C) -> Result<Plan, ApiError<C::Error>>
+    where
+        C: Client,
+    {
+        let rsp: RetrieveResponse = RetrieveDns::builder()
+            .domain(self.domain.as_str())
+            .build()
+            .map_err(ApiError::endpoint)?
+            .query(client)?;
+        Ok(self.diff(&rsp.records))
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use serde_json::json;
+
+    use crate::{api::ApiError, endpoints::DnsContent, test::FakePorkbun, Record};
+
+    use super::{Change, DesiredRecord, DesiredZone};
+
+    fn record(id: &str, name: &str, record_type: &str, content: &str, ttl: u32) -> Record {
+        Record {
+            id: id.into(),
+            name: name.into(),
+            record_type: record_type.into(),
+            content: content.into(),
+            ttl: ttl.to_string(),
+            priority: Some("0".into()),
+            notes: None,
+        }
+    }
+
+    fn a(content: &str) -> DesiredRecord {
+        DesiredRecord::new(DnsContent::A {
+            content: content.parse().unwrap(),
+        })
+    }
+
+    fn current() -> Vec<Record> {
+        vec![
+            record("1", "example.com", "A", "192.0.2.1", 600),
+            record("2", "www.example.com", "CNAME", "example.com", 600),
+            record("3", "example.com", "NS", "curitiba.ns.porkbun.com", 86400),
+            record("4", "old.example.com", "A", "192.0.2.9", 600),
+        ]
+    }
+
+    fn summary(changes: &[Change]) -> Vec<String> {
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Delete { current } => format!("delete {}", current.id),
+                Change::Edit { current, .. } => format!("edit {}", current.id),
+                Change::Create { desired } => format!("create {}", desired.record.content()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn up_to_date() {
+        let zone = DesiredZone::new(
+            "example.com",
+            vec![
+                a("192.0.2.1"),
+                DesiredRecord::new(DnsContent::Cname {
+                    content: "Example.com.".into(),
+                })
+                .name("www")
+                .ttl(600),
+                a("192.0.2.9").name("old"),
+            ],
+        );
+        assert!(zone.diff(&current()).is_empty());
+    }
+
+    #[test]
+    fn name_forms() {
+        let zone = DesiredZone::new(
+            "Example.com.",
+            vec![
+                a("192.0.2.1").name("@"),
+                DesiredRecord::new(DnsContent::Cname {
+                    content: "example.com".into(),
+                })
+                .name("WWW.example.com."),
+                a("192.0.2.9").name("old.example.com"),
+            ],
+        );
+        let mut current = current();
+        current[1].name = "WWW.Example.com.".into();
+        assert!(zone.diff(&current).is_empty());
+
+        let record = DesiredRecord::from_record("example.com", &current[1]).unwrap();
+        assert_eq!(record.name.as_deref(), Some("www"));
+        let record = DesiredRecord::from_record("example.com.", &current[0]).unwrap();
+        assert_eq!(record.name, None);
+    }
+
+    #[test]
+    fn changes() {
+        let zone = DesiredZone::new(
+            "example.com",
+            vec![
+                a("192.0.2.2"),
+                a("192.0.2.3"),
+                DesiredRecord::new(DnsContent::Cname {
+                    content: "example.com".into(),
+                })
+                .name("www")
+                .ttl(3600),
+            ],
+        );
+        let plan = zone.diff(&current());
+        assert_eq!(
+            summary(&plan.changes),
+            ["delete 4", "edit 1", "edit 2", "create 192.0.2.3"],
+        );
+        assert_eq!(
+            plan.to_string(),
+            "- old.example.com 600 A 192.0.2.9\n\
+             ~ example.com - A 192.0.2.2 (was example.com 600 A 192.0.2.1)\n\
+             ~ www.example.com 3600 CNAME example.com (was www.example.com 600 CNAME example.com)\n\
+             + example.com - A 192.0.2.3\n",
+        );
+    }
+
+    #[test]
+    fn keep_unmanaged() {
+        let zone =
+            DesiredZone::new("example.com", vec![a("192.0.2.1"), a("192.0.2.2")]).keep_unmanaged();
+        let plan = zone.diff(&current());
+        assert_eq!(summary(&plan.changes), ["create 192.0.2.2"]);
+    }
+
+    #[test]
+    fn serialize() {
+        let zone = DesiredZone::new(
+            "example.com",
+            vec![DesiredRecord::new(DnsContent::Mx {
+                content: "mail.example.com".into(),
+                priority: 10,
+            })],
+        )
+        .keep_unmanaged();
+        let plan = zone.diff(&[]);
+        assert_eq!(
+            serde_json::to_value(&plan).unwrap(),
+            json!({
+                "domain": "example.com",
+                "changes": [{
+                    "action": "create",
+                    "desired": {"type": "MX", "content": "mail.example.com", "priority": 10},
+                }],
+            }),
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let zone = DesiredZone::new("example.com", vec![a("192.0.2.1")]);
+        zone.plan(&fake).unwrap().apply(&fake).unwrap();
+
+        let zone = DesiredZone::new(
+            "example.com",
+            vec![a("192.0.2.2"), a("192.0.2.3").name("www").ttl(3600)],
+        );
+        let plan = zone.plan(&fake).unwrap();
+        assert_eq!(
+            summary(&plan.changes),
+            ["edit 250000000", "create 192.0.2.3"]
+        );
+        plan.apply(&fake).unwrap();
+
+        assert!(zone.plan(&fake).unwrap().is_empty());
+        let records = fake.records("example.com").unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].ttl, "3600");
+    }
+
+    #[test]
+    fn apply_invalid_name() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let zone = DesiredZone::new(
+            "example.com",
+            vec![a("192.0.2.1"), a("192.0.2.2").name("www..dev")],
+        );
+        let err = zone.plan(&fake).unwrap().apply(&fake).unwrap_err();
+        assert_eq!(err.applied, 1);
+        assert!(matches!(err.source, ApiError::Endpoint { .. }));
+        assert_eq!(fake.records("example.com").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,676 @@
+use crate::{
+    api::{ApiError, AsyncClient, Client},
+    auth::{Auth, CREDENTIAL_FIELDS},
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{request::Builder as RequestBuilder, Method, Response, StatusCode, Uri};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use thiserror::Error;
+use url::Url;
+use zeroize::Zeroizing;
+
+const REPLAY_STUB: &str = "https://porkbun.invalid/api/json/v3/";
+
+/// Errors which may occur when loading or saving a cassette.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CassetteError {
+    /// The cassette file could not be read or written.
+    #[error("cassette {}: {}", path.display(), source)]
+    Io {
+        /// The path of the cassette.
+        path: PathBuf,
+        /// The source of the error.
+        source: io::Error,
+    },
+    /// The cassette is not valid JSON.
+    #[error("cassette {}: {}", path.display(), source)]
+    Json {
+        /// The path of the cassette.
+        path: PathBuf,
+        /// The source of the error.
+        source: serde_json::Error,
+    },
+    /// The cassette is not valid YAML.
+    #[cfg(feature = "yaml")]
+    #[error("cassette {}: {}", path.display(), source)]
+    Yaml {
+        /// The path of the cassette.
+        path: PathBuf,
+        /// The source of the error.
+        source: serde_yaml::Error,
+    },
+    /// YAML cassettes need the `yaml` feature.
+    #[error("cassette {}: YAML cassettes need the `yaml` feature", path.display())]
+    YamlUnsupported {
+        /// The path of the cassette.
+        path: PathBuf,
+    },
+}
+
+/// The body of a recorded request or response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Body {
+    /// A JSON body.
+    Json(Value),
+    /// Any other body.
+    Text(String),
+}
+
+impl Body {
+    /// The body of a request, without the credentials.
+    ///
+    /// A body which only carried credentials is no body at all.
+    fn request(data: &[u8]) -> Option<Self> {
+        match Self::response(data)? {
+            Body::Json(Value::Object(mut body)) => {
+                for field in CREDENTIAL_FIELDS.iter() {
+                    body.remove(*field);
+                }
+                if body.is_empty() {
+                    None
+                } else {
+                    Some(Body::Json(Value::Object(body)))
+                }
+            }
+            body => Some(body),
+        }
+    }
+
+    /// The body of a response.
+    fn response(data: &[u8]) -> Option<Self> {
+        if data.is_empty() {
+            return None;
+        }
+
+        Some(match serde_json::from_slice(data) {
+            Ok(value) => Body::Json(value),
+            Err(_) => Body::Text(String::from_utf8_lossy(data).into_owned()),
+        })
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Body::Json(value) => serde_json::to_vec(&value).expect("a JSON value is serializable"),
+            Body::Text(text) => text.into_bytes(),
+        }
+    }
+}
+
+/// A request as recorded in a cassette, with the credentials scrubbed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    /// The HTTP method.
+    pub method: String,
+    /// The path of the endpoint, relative to the API root.
+    pub endpoint: String,
+    /// The body, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Body>,
+}
+
+/// A response as recorded in a cassette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// The HTTP status.
+    #[serde(deserialize_with = "status_code")]
+    pub status: u16,
+    /// The body, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Body>,
+}
+
+fn status_code<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let status = u16::deserialize(deserializer)?;
+    StatusCode::from_u16(status).map_err(de::Error::custom)?;
+    Ok(status)
+}
+
+/// A request and the response Porkbun gave to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The request.
+    pub request: RecordedRequest,
+    /// The response.
+    pub response: RecordedResponse,
+}
+
+/// A sequence of recorded interactions.
+///
+/// Cassettes are stored as YAML when their file name ends in `.yaml` or `.yml`, and as JSON
+/// otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    /// The interactions, in the order they happened.
+    pub interactions: Vec<Interaction>,
+}
+
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml") | Some("yml")
+    )
+}
+
+#[cfg(feature = "yaml")]
+fn from_yaml(path: &Path, data: &[u8]) -> Result<Cassette, CassetteError> {
+    serde_yaml::from_slice(data).map_err(|source| CassetteError::Yaml {
+        path: path.into(),
+        source,
+    })
+}
+
+#[cfg(not(feature = "yaml"))]
+fn from_yaml(path: &Path, _: &[u8]) -> Result<Cassette, CassetteError> {
+    Err(CassetteError::YamlUnsupported { path: path.into() })
+}
+
+#[cfg(feature = "yaml")]
+fn to_yaml(path: &Path, cassette: &Cassette) -> Result<Vec<u8>, CassetteError> {
+    serde_yaml::to_vec(cassette).map_err(|source| CassetteError::Yaml {
+        path: path.into(),
+        source,
+    })
+}
+
+#[cfg(not(feature = "yaml"))]
+fn to_yaml(path: &Path, _: &Cassette) -> Result<Vec<u8>, CassetteError> {
+    Err(CassetteError::YamlUnsupported { path: path.into() })
+}
+
+impl Cassette {
+    /// Load a cassette from a file.
+    pub fn load<P>(path: P) -> Result<Self, CassetteError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|source| CassetteError::Io {
+            path: path.into(),
+            source,
+        })?;
+
+        if is_yaml(path) {
+            return from_yaml(path, &data);
+        }
+
+        serde_json::from_slice(&data).map_err(|source| CassetteError::Json {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// Save the cassette to a file.
+    pub fn save<P>(&self, path: P) -> Result<(), CassetteError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let data = if is_yaml(path) {
+            to_yaml(path, self)?
+        } else {
+            serde_json::to_vec_pretty(self).map_err(|source| CassetteError::Json {
+                path: path.into(),
+                source,
+            })?
+        };
+
+        fs::write(path, data).map_err(|source| CassetteError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+}
+
+/// A client which records every request sent through another client.
+///
+/// The credentials are scrubbed from the recorded requests. Call [`RecordingClient::save`] once
+/// done to write the cassette.
+pub struct RecordingClient<C> {
+    client: C,
+    base: Url,
+    cassette: Mutex<Cassette>,
+}
+
+impl<C> RecordingClient<C> {
+    /// Record the requests sent through `client`.
+    pub fn new(client: C) -> Result<Self, ApiError<C::Error>>
+    where
+        C: Client,
+    {
+        let base = Client::rest_endpoint(&client, "")?;
+        Ok(Self::with_base(client, base))
+    }
+
+    /// Record the requests sent through an asynchronous `client`.
+    pub fn new_async(client: C) -> Result<Self, ApiError<<C as AsyncClient>::Error>>
+    where
+        C: AsyncClient,
+    {
+        let base = AsyncClient::rest_endpoint(&client, "")?;
+        Ok(Self::with_base(client, base))
+    }
+
+    fn with_base(client: C, base: Url) -> Self {
+        Self {
+            client,
+            base,
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+
+    /// The interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    /// Write the interactions recorded so far to a file.
+    pub fn save<P>(&self, path: P) -> Result<(), CassetteError>
+    where
+        P: AsRef<Path>,
+    {
+        self.cassette.lock().unwrap().save(path)
+    }
+
+    fn record(&self, method: &Method, uri: &Uri, body: &[u8], rsp: &Response<Bytes>) {
+        let uri = uri.to_string();
+        let endpoint = uri.strip_prefix(self.base.as_str()).unwrap_or(&uri).into();
+
+        self.cassette
+            .lock()
+            .unwrap()
+            .interactions
+            .push(Interaction {
+                request: RecordedRequest {
+                    method: method.to_string(),
+                    endpoint,
+                    body: Body::request(body),
+                },
+                response: RecordedResponse {
+                    status: rsp.status().as_u16(),
+                    body: Body::response(rsp.body()),
+                },
+            });
+    }
+}
+
+impl<C> Client for RecordingClient<C>
+where
+    C: Client,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        self.client.rest_endpoint(endpoint)
+    }
+
+    fn rest(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        let method = request.method_ref().unwrap().clone();
+        let uri = request.uri_ref().unwrap().clone();
+        let sent = Zeroizing::new(body.clone());
+        let rsp = self.client.rest(request, body)?;
+        self.record(&method, &uri, &sent, &rsp);
+        Ok(rsp)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        self.client.auth()
+    }
+}
+
+#[async_trait]
+impl<C> AsyncClient for RecordingClient<C>
+where
+    C: AsyncClient + Sync,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        self.client.rest_endpoint(endpoint)
+    }
+
+    async fn rest_async(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        let method = request.method_ref().unwrap().clone();
+        let uri = request.uri_ref().unwrap().clone();
+        let sent = Zeroizing::new(body.clone());
+        let rsp = self.client.rest_async(request, body).await?;
+        self.record(&method, &uri, &sent, &rsp);
+        Ok(rsp)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        self.client.auth()
+    }
+}
+
+/// Errors raised by a [`ReplayClient`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ReplayError {
+    /// No interaction left in the cassette matches the request.
+    #[error("no recorded interaction matches {} {}", method, endpoint)]
+    Unmatched {
+        /// The HTTP method of the request.
+        method: Method,
+        /// The path of the endpoint, relative to the API root.
+        endpoint: String,
+    },
+    /// The matching interaction recorded an invalid HTTP status.
+    #[error("recorded an invalid status code: {}", status)]
+    InvalidStatus {
+        /// The recorded status.
+        status: u16,
+    },
+}
+
+/// A client which answers requests from a cassette.
+///
+/// A request is answered by the first interaction not used yet with the same method, endpoint
+/// and body; credentials are ignored. Requests without a matching interaction fail with
+/// [`ReplayError::Unmatched`]. Placeholder credentials are sent like a real client would.
+pub struct ReplayClient {
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+fn placeholder_credentials() -> Auth {
+    Auth::new("replay".into(), "replay".into())
+}
+
+impl ReplayClient {
+    /// Replay the interactions of a cassette.
+    pub fn new(cassette: Cassette) -> Self {
+        Self {
+            interactions: Mutex::new(cassette.interactions.into_iter().map(Some).collect()),
+        }
+    }
+
+    /// Replay the interactions of a cassette file.
+    pub fn load<P>(path: P) -> Result<Self, CassetteError>
+    where
+        P: AsRef<Path>,
+    {
+        Cassette::load(path).map(Self::new)
+    }
+
+    /// The number of interactions which were not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|interaction| interaction.is_some())
+            .count()
+    }
+
+    fn replay(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<ReplayError>> {
+        let method = request.method_ref().unwrap().clone();
+        let uri = request.uri_ref().unwrap().to_string();
+        let recorded = RecordedRequest {
+            method: method.to_string(),
+            endpoint: uri.strip_prefix(REPLAY_STUB).unwrap_or(&uri).into(),
+            body: Body::request(&body),
+        };
+
+        let mut interactions = self.interactions.lock().unwrap();
+        let interaction = interactions
+            .iter_mut()
+            .find(|interaction| {
+                interaction
+                    .as_ref()
+                    .is_some_and(|interaction| interaction.request == recorded)
+            })
+            .and_then(Option::take)
+            .ok_or_else(|| {
+                ApiError::client(ReplayError::Unmatched {
+                    method,
+                    endpoint: recorded.endpoint.clone(),
+                })
+            })?;
+
+        let status = interaction.response.status;
+        let status = StatusCode::from_u16(status)
+            .map_err(|_| ApiError::client(ReplayError::InvalidStatus { status }))?;
+        let body = interaction
+            .response
+            .body
+            .map(Body::into_bytes)
+            .unwrap_or_default();
+        Ok(Response::builder()
+            .status(status)
+            .body(body.into())
+            .unwrap())
+    }
+}
+
+impl Client for ReplayClient {
+    type Error = ReplayError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        Ok(Url::parse(REPLAY_STUB)?.join(endpoint)?)
+    }
+
+    fn rest(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        self.replay(request, body)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(placeholder_credentials())
+    }
+}
+
+#[async_trait]
+impl AsyncClient for ReplayClient {
+    type Error = ReplayError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        <Self as Client>::rest_endpoint(self, endpoint)
+    }
+
+    async fn rest_async(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        self.replay(request, body)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(placeholder_credentials())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{Method, StatusCode};
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    use crate::{
+        api::{self, ApiError, AsyncQuery, Query},
+        endpoints::{DnsContent, EditDns, Ping},
+        test::client::{ExpectedUrl, TestClient},
+        PingResponse,
+    };
+
+    use super::{Body, Cassette, CassetteError, RecordingClient, ReplayClient, ReplayError};
+
+    fn cassette_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("porkbun-rs-cassette-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    fn edit() -> EditDns<'static> {
+        EditDns::builder()
+            .domain("example.com")
+            .id("1234")
+            .record(DnsContent::Txt {
+                content: "hello".into(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn record() -> Cassette {
+        let client = RecordingClient::new(
+            TestClient::new()
+                .expect_json(
+                    ExpectedUrl::builder()
+                        .method(Method::POST)
+                        .endpoint("ping")
+                        .content_type("application/json")
+                        .body_json(&json!({}))
+                        .build()
+                        .unwrap(),
+                    &json!({"status": "SUCCESS", "yourIp": "127.0.0.1"}),
+                )
+                .expect_json(
+                    ExpectedUrl::builder()
+                        .method(Method::POST)
+                        .endpoint("dns/edit/example.com/1234")
+                        .content_type("application/json")
+                        .body_json(&json!({"type": "TXT", "content": "hello"}))
+                        .status(StatusCode::BAD_REQUEST)
+                        .build()
+                        .unwrap(),
+                    &json!({"status": "ERROR", "message": "Invalid record ID."}),
+                ),
+        )
+        .unwrap();
+
+        let _: PingResponse = Ping::builder().build().unwrap().query(&client).unwrap();
+        api::ignore(edit()).query(&client).unwrap_err();
+
+        client.cassette()
+    }
+
+    #[test]
+    fn credentials_are_scrubbed() {
+        let cassette = record();
+
+        assert_eq!(cassette.interactions.len(), 2);
+        assert_eq!(cassette.interactions[0].request.endpoint, "ping");
+        assert_eq!(cassette.interactions[0].request.body, None);
+        assert_eq!(
+            cassette.interactions[1].request.body,
+            Some(Body::Json(json!({"type": "TXT", "content": "hello"}))),
+        );
+        assert_eq!(cassette.interactions[1].response.status, 400);
+        let saved = serde_json::to_string(&cassette).unwrap();
+        assert!(!saved.contains("test-apikey"));
+        assert!(!saved.contains("test-secretapikey"));
+    }
+
+    #[test]
+    fn replay() {
+        let path = cassette_path("replay.json");
+        record().save(&path).unwrap();
+        let client = ReplayClient::load(&path).unwrap();
+
+        let err = api::ignore(edit()).query(&client).unwrap_err();
+        if let ApiError::PorkBun { message, .. } = err {
+            assert_eq!(message, "Invalid record ID.");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+        let rsp: PingResponse = Ping::builder().build().unwrap().query(&client).unwrap();
+        assert_eq!(rsp.your_ip, "127.0.0.1");
+        assert_eq!(client.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn replay_unmatched() {
+        let client = ReplayClient::new(record());
+
+        let other = EditDns::builder()
+            .domain("example.com")
+            .id("1234")
+            .record(DnsContent::Txt {
+                content: "goodbye".into(),
+            })
+            .build()
+            .unwrap();
+        let err = api::ignore(other).query_async(&client).await.unwrap_err();
+        if let ApiError::Client {
+            source: ReplayError::Unmatched { method, endpoint },
+        } = err
+        {
+            assert_eq!(method, Method::POST);
+            assert_eq!(endpoint, "dns/edit/example.com/1234");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+        assert_eq!(client.remaining(), 2);
+    }
+
+    #[test]
+    fn invalid_status() {
+        let path = cassette_path("invalid-status.json");
+        let mut cassette = serde_json::to_value(record()).unwrap();
+        cassette["interactions"][0]["response"]["status"] = json!(1000);
+        std::fs::write(&path, cassette.to_string()).unwrap();
+
+        let err = Cassette::load(&path).unwrap_err();
+        assert!(matches!(err, CassetteError::Json { .. }), "{}", err);
+        assert!(err.to_string().contains("invalid status code"), "{}", err);
+    }
+
+    #[test]
+    fn replay_invalid_status() {
+        let mut cassette = record();
+        cassette.interactions[0].response.status = 1000;
+        let client = ReplayClient::new(cassette);
+
+        let err = Ping::builder()
+            .build()
+            .unwrap()
+            .query(&client)
+            .map(|_: PingResponse| ())
+            .unwrap_err();
+        if let ApiError::Client {
+            source: ReplayError::InvalidStatus { status },
+        } = err
+        {
+            assert_eq!(status, 1000);
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        let path = cassette_path("replay.yaml");
+        let cassette = record();
+        cassette.save(&path).unwrap();
+
+        assert_eq!(Cassette::load(&path).unwrap(), cassette);
+    }
+}
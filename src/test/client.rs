@@ -1,4 +1,7 @@
-use crate::api::{ApiError, AsyncClient, Client};
+use crate::{
+    api::{ApiError, AsyncClient, Client},
+    auth::{Auth, CREDENTIAL_FIELDS},
+};
 use async_trait::async_trait;
 use bytes::Bytes;
 use derive_builder::Builder;
@@ -6,21 +9,33 @@ use http::request::Builder as RequestBuilder;
 use http::{header, Method, Response, StatusCode};
 use serde::Serialize;
 use serde_json::{Map, Value};
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    thread,
+};
 use thiserror::Error;
 use url::Url;
 
+/// A request a test client expects to receive, and the status to answer it with.
 #[derive(Debug, Builder)]
 pub struct ExpectedUrl {
+    /// The HTTP method of the request.
     #[builder(default = "Method::GET")]
     pub method: Method,
+    /// The path of the endpoint, relative to the API root.
     pub endpoint: &'static str,
+    /// The query parameters of the request.
     #[builder(default)]
     pub query: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    /// The content type of the request, if it has a body.
     #[builder(setter(strip_option, into), default)]
     pub content_type: Option<String>,
+    /// The body of the request, without the credentials.
     #[builder(default)]
     pub body: Vec<u8>,
+    /// The status of the response.
     #[builder(default = "StatusCode::OK")]
     pub status: StatusCode,
 }
@@ -55,9 +70,27 @@ impl ExpectedUrl {
         assert_eq!(count, self.query.len());
         assert_eq!(url.fragment(), None);
     }
+
+    fn check_content_type(&self, request: &RequestBuilder) {
+        let headers = request.headers_ref().unwrap();
+        let content_type = headers
+            .get_all(header::CONTENT_TYPE)
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect::<Vec<_>>();
+        let expected = self.content_type.iter().collect::<Vec<_>>();
+        assert_eq!(content_type, expected, "unexpected content type");
+    }
+
+    fn check_request(&self, request: &RequestBuilder) {
+        let url = Url::parse(&format!("{}", request.uri_ref().unwrap())).unwrap();
+        self.check(request.method_ref().unwrap().clone(), &url);
+        self.check_content_type(request);
+    }
 }
 
 impl ExpectedUrlBuilder {
+    /// Expect a JSON body.
     pub fn body_json<T>(&mut self, body: &T) -> &mut Self
     where
         T: Serialize,
@@ -89,6 +122,9 @@ struct MockClient {
 
 const CLIENT_STUB: &str = "https://porkbun.invalid/api/json/v3";
 
+/// A client which expects exactly one request and answers it with canned data.
+///
+/// It sends no credentials.
 pub struct SingleTestClient {
     client: MockClient,
 
@@ -96,6 +132,7 @@ pub struct SingleTestClient {
 }
 
 impl SingleTestClient {
+    /// Answer the expected request with `data`.
     pub fn new_raw<T>(expected: ExpectedUrl, data: T) -> Self
     where
         T: Into<Vec<u8>>,
@@ -116,6 +153,7 @@ impl SingleTestClient {
         Self { client, expected }
     }
 
+    /// Answer the expected request with `data` serialized as JSON.
     pub fn new_json<T>(expected: ExpectedUrl, data: &T) -> Self
     where
         T: Serialize,
@@ -125,6 +163,7 @@ impl SingleTestClient {
     }
 }
 
+/// The error type of the test clients, which never fail.
 #[derive(Debug, Error)]
 #[error("test client error")]
 pub enum TestClientError {}
@@ -141,9 +180,7 @@ impl Client for SingleTestClient {
         request: RequestBuilder,
         body: Vec<u8>,
     ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
-        let url = Url::parse(&format!("{}", request.uri_ref().unwrap())).unwrap();
-        self.expected
-            .check(request.method_ref().unwrap().clone(), &url);
+        self.expected.check_request(&request);
         assert_eq!(
             &body,
             &self.expected.body,
@@ -151,16 +188,6 @@ impl Client for SingleTestClient {
             String::from_utf8_lossy(&body),
             String::from_utf8_lossy(&self.expected.body),
         );
-        let headers = request.headers_ref().unwrap();
-        let content_type = headers
-            .get_all(header::CONTENT_TYPE)
-            .iter()
-            .map(|value| value.to_str().unwrap());
-        if let Some(expected_content_type) = self.expected.content_type.as_ref() {
-            itertools::assert_equal(content_type, [expected_content_type].iter().cloned());
-        } else {
-            assert_eq!(content_type.count(), 0);
-        }
 
         let request = request.body(body).unwrap();
 
@@ -173,8 +200,8 @@ impl Client for SingleTestClient {
             .map(Into::into))
     }
 
-    fn auth(&self) -> Map<String, Value> {
-        Map::default()
+    fn auth(&self) -> Option<Auth> {
+        None
     }
 }
 
@@ -194,7 +221,309 @@ impl AsyncClient for SingleTestClient {
         <Self as Client>::rest(self, request, body)
     }
 
-    fn auth(&self) -> Map<String, Value> {
-        Map::default()
+    fn auth(&self) -> Option<Auth> {
+        None
+    }
+}
+
+/// A client which expects a sequence of requests, in order, and answers each with canned data.
+///
+/// It sends placeholder credentials like a real client would. They are masked before the body
+/// is compared, so expected bodies leave them out; JSON bodies are compared as values, so the
+/// order of their fields does not matter.
+///
+/// Dropping the client while some expected requests were not received panics, unless the
+/// thread is already panicking. [`TestClient::assert_done`] checks the same thing explicitly.
+#[derive(Default)]
+pub struct TestClient {
+    expected: Mutex<VecDeque<(ExpectedUrl, MockResponse)>>,
+}
+
+impl TestClient {
+    /// Create a client expecting no requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect another request and answer it with `data`.
+    pub fn expect_raw<T>(self, expected: ExpectedUrl, data: T) -> Self
+    where
+        T: Into<Vec<u8>>,
+    {
+        let response = MockResponse {
+            status: expected.status,
+            data: data.into(),
+        };
+        self.expected
+            .lock()
+            .unwrap()
+            .push_back((expected, response));
+        self
+    }
+
+    /// Expect another request and answer it with `data` serialized as JSON.
+    pub fn expect_json<T>(self, expected: ExpectedUrl, data: &T) -> Self
+    where
+        T: Serialize,
+    {
+        let data = serde_json::to_vec(data).unwrap();
+        self.expect_raw(expected, data)
+    }
+
+    /// The number of expected requests which were not received yet.
+    pub fn remaining(&self) -> usize {
+        self.expected.lock().unwrap().len()
+    }
+
+    /// Panic if some expected requests were not received.
+    pub fn assert_done(&self) {
+        let expected = self.expected.lock().unwrap();
+        if !expected.is_empty() {
+            let endpoints = expected
+                .iter()
+                .map(|(expected, _)| format!("{} {}", expected.method, expected.endpoint))
+                .collect::<Vec<_>>();
+            panic!(
+                "{} expected request(s) were not received: {}",
+                endpoints.len(),
+                endpoints.join(", "),
+            );
+        }
+    }
+
+    fn credentials() -> Map<String, Value> {
+        CREDENTIAL_FIELDS
+            .iter()
+            .map(|field| (field.to_string(), Value::from(format!("test-{}", field))))
+            .collect()
+    }
+
+    fn check_body(expected: &ExpectedUrl, body: &[u8]) {
+        let actual = serde_json::from_slice::<Value>(body);
+        let wanted = serde_json::from_slice::<Value>(&expected.body);
+        match (actual, wanted) {
+            (Ok(Value::Object(mut actual)), Ok(wanted)) => {
+                for (field, value) in Self::credentials() {
+                    assert_eq!(
+                        actual.remove(&field),
+                        Some(value),
+                        "credential `{}` was not sent",
+                        field,
+                    );
+                }
+                let actual = Value::Object(actual);
+                assert_eq!(
+                    actual, wanted,
+                    "\nbody of {} {} is not the same:\nactual  : {}\nexpected: {}\n",
+                    expected.method, expected.endpoint, actual, wanted,
+                );
+            }
+            _ => assert_eq!(
+                body,
+                &expected.body[..],
+                "\nbody of {} {} is not the same:\nactual  : {}\nexpected: {}\n",
+                expected.method,
+                expected.endpoint,
+                String::from_utf8_lossy(body),
+                String::from_utf8_lossy(&expected.body),
+            ),
+        }
+    }
+}
+
+impl Drop for TestClient {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            self.assert_done();
+        }
+    }
+}
+
+impl Client for TestClient {
+    type Error = TestClientError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        Ok(Url::parse(&format!("{}/{}", CLIENT_STUB, endpoint))?)
+    }
+
+    fn rest(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        let (expected, response) = self
+            .expected
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| {
+                panic!(
+                    "unexpected request: {} {}",
+                    request.method_ref().unwrap(),
+                    request.uri_ref().unwrap(),
+                )
+            });
+
+        expected.check_request(&request);
+        Self::check_body(&expected, &body);
+
+        Ok(response.response().map(Into::into))
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        let credential = |field| format!("test-{}", field);
+        Some(Auth::new(
+            credential(CREDENTIAL_FIELDS[0]).into(),
+            credential(CREDENTIAL_FIELDS[1]).into(),
+        ))
+    }
+}
+
+#[async_trait]
+impl AsyncClient for TestClient {
+    type Error = TestClientError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        <Self as Client>::rest_endpoint(self, endpoint)
+    }
+
+    async fn rest_async(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        <Self as Client>::rest(self, request, body)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        let credential = |field| format!("test-{}", field);
+        Some(Auth::new(
+            credential(CREDENTIAL_FIELDS[0]).into(),
+            credential(CREDENTIAL_FIELDS[1]).into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::{
+        api::{self, AsyncQuery, Query},
+        endpoints::{CreateDns, DeleteDns, DnsContent},
+        CreateResponse,
+    };
+
+    use super::{ExpectedUrl, TestClient};
+
+    fn create() -> CreateDns<'static> {
+        CreateDns::builder()
+            .domain("example.com")
+            .name("www")
+            .record(DnsContent::Cname {
+                content: "example.com".into(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn expected_create() -> ExpectedUrl {
+        ExpectedUrl::builder()
+            .method(Method::POST)
+            .endpoint("dns/create/example.com")
+            .content_type("application/json")
+            .body_json(&json!({
+                "type": "CNAME",
+                "content": "example.com",
+                "name": "www",
+            }))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn queue() {
+        let client = TestClient::new()
+            .expect_json(expected_create(), &json!({"status": "SUCCESS", "id": "1"}))
+            .expect_json(
+                ExpectedUrl::builder()
+                    .method(Method::POST)
+                    .endpoint("dns/delete/example.com/1")
+                    .content_type("application/json")
+                    .body_json(&json!({}))
+                    .build()
+                    .unwrap(),
+                &json!({"status": "SUCCESS"}),
+            );
+
+        let rsp: CreateResponse = create().query(&client).unwrap();
+        assert_eq!(rsp.id, "1");
+        assert_eq!(client.remaining(), 1);
+
+        let delete = DeleteDns::builder()
+            .domain("example.com")
+            .id(rsp.id)
+            .build()
+            .unwrap();
+        api::ignore(delete).query(&client).unwrap();
+        client.assert_done();
+    }
+
+    #[tokio::test]
+    async fn queue_async() {
+        let client = TestClient::new().expect_json(
+            ExpectedUrl::builder()
+                .method(Method::POST)
+                .endpoint("dns/create/example.com")
+                .content_type("application/json")
+                .body_json(&json!({
+                    "name": "www",
+                    "type": "CNAME",
+                    "content": "example.com",
+                }))
+                .status(StatusCode::BAD_REQUEST)
+                .build()
+                .unwrap(),
+            &json!({"status": "ERROR", "message": "Invalid type."}),
+        );
+
+        let err = api::ignore(create())
+            .query_async(&client)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "porkbun server error: Invalid type. status: ERROR",
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "1 expected request(s) were not received: POST dns/create/example.com"
+    )]
+    fn unmet_expectations() {
+        let _client = TestClient::new().expect_raw(expected_create(), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected request: POST")]
+    fn unexpected_request() {
+        let client = TestClient::new();
+        api::ignore(create()).query(&client).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "body of POST dns/create/example.com is not the same")]
+    fn wrong_body() {
+        let client = TestClient::new().expect_raw(expected_create(), "");
+        let create = CreateDns::builder()
+            .domain("example.com")
+            .record(DnsContent::Cname {
+                content: "example.com".into(),
+            })
+            .build()
+            .unwrap();
+        api::ignore(create).query(&client).unwrap();
     }
 }
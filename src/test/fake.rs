@@ -0,0 +1,737 @@
+use crate::{
+    api::{ApiError, AsyncClient, Client},
+    auth::{Auth, CREDENTIAL_FIELDS},
+    endpoints::LIST_ALL_PAGE_SIZE,
+    types::Record,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{request::Builder as RequestBuilder, Method, Response, StatusCode};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+use url::Url;
+
+use super::client::TestClientError;
+
+const API_PATH: &str = "/api/json/v3/";
+const FAKE_STUB: &str = "https://porkbun.invalid/api/json/v3/";
+const FIRST_ID: u64 = 250_000_000;
+const DEFAULT_TTL: u32 = 600;
+const RECORD_TYPES: &[&str] = &[
+    "A", "AAAA", "ALIAS", "CAA", "CNAME", "HTTPS", "MX", "NS", "SRV", "SVCB", "TLSA", "TXT",
+];
+
+#[derive(Debug)]
+struct State {
+    domains: BTreeMap<String, Vec<Record>>,
+    next_id: u64,
+    your_ip: String,
+    credentials: Option<(String, String)>,
+}
+
+/// An in-memory stand-in for Porkbun, for end-to-end tests.
+///
+/// It keeps the DNS records of its domains and implements the `ping`, `domain/listAll`,
+/// `dns/create`, `dns/edit`, `dns/delete` and `dns/retrieve` endpoints, answering like
+/// Porkbun does. It can
+/// be queried directly as a client, or served over HTTP with [`FakePorkbun::serve`] (HTTPS
+/// with `serve_tls` and the `native-tls` feature) for the [`Porkbun`](crate::Porkbun) clients
+/// to talk to.
+///
+/// Record ids are handed out in increasing order. Any credentials are accepted unless
+/// [`FakePorkbun::with_credentials`] was used.
+#[derive(Debug, Clone)]
+pub struct FakePorkbun {
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for FakePorkbun {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                domains: BTreeMap::new(),
+                next_id: FIRST_ID,
+                your_ip: Ipv4Addr::LOCALHOST.to_string(),
+                credentials: None,
+            })),
+        }
+    }
+}
+
+type Reply = (StatusCode, Value);
+
+fn error(message: &str) -> Reply {
+    (
+        StatusCode::BAD_REQUEST,
+        json!({
+            "status": "ERROR",
+            "message": message,
+        }),
+    )
+}
+
+fn success(mut fields: Value) -> Reply {
+    fields["status"] = "SUCCESS".into();
+    (StatusCode::OK, fields)
+}
+
+fn field(body: &Map<String, Value>, name: &str) -> Option<String> {
+    match body.get(name)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+impl FakePorkbun {
+    /// Create a fake without any domain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a domain without any record.
+    pub fn with_domain<S>(self, domain: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.state
+            .lock()
+            .unwrap()
+            .domains
+            .entry(domain.into().to_lowercase())
+            .or_default();
+        self
+    }
+
+    /// Only accept these credentials.
+    pub fn with_credentials<K, S>(self, apikey: K, secretapikey: S) -> Self
+    where
+        K: Into<String>,
+        S: Into<String>,
+    {
+        self.state.lock().unwrap().credentials = Some((apikey.into(), secretapikey.into()));
+        self
+    }
+
+    /// The address `ping` reports.
+    pub fn with_your_ip<S>(self, ip: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.state.lock().unwrap().your_ip = ip.into();
+        self
+    }
+
+    /// The records of a domain, or `None` if there is no such domain.
+    pub fn records(&self, domain: &str) -> Option<Vec<Record>> {
+        self.state
+            .lock()
+            .unwrap()
+            .domains
+            .get(&domain.to_lowercase())
+            .cloned()
+    }
+
+    /// Answer a request for an endpoint, given its path relative to the API root.
+    pub fn handle(&self, method: &Method, endpoint: &str, body: &[u8]) -> (StatusCode, Value) {
+        if method != Method::POST {
+            return error("All HTTP request must use POST.");
+        }
+
+        let body = match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Object(body)) => body,
+            _ => return error("Invalid JSON."),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if let Some((apikey, secretapikey)) = &state.credentials {
+            let sent = (
+                field(&body, CREDENTIAL_FIELDS[0]),
+                field(&body, CREDENTIAL_FIELDS[1]),
+            );
+            if sent != (Some(apikey.clone()), Some(secretapikey.clone())) {
+                return error("Invalid API key. (002)");
+            }
+        }
+
+        let segments = endpoint
+            .trim_matches('/')
+            .split('/')
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+        match segments.as_slice() {
+            ["ping"] => success(json!({ "yourIp": state.your_ip })),
+            ["domain", "listall"] => state.list_all(&body),
+            ["dns", "create", domain] => state.create(domain, &body),
+            ["dns", "edit", domain, id] => state.edit(domain, id, &body),
+            ["dns", "delete", domain, id] => state.delete(domain, id),
+            ["dns", "retrieve", domain] => state.retrieve(domain, None),
+            ["dns", "retrieve", domain, id] => state.retrieve(domain, Some(id)),
+            _ => error("Invalid API endpoint."),
+        }
+    }
+
+    /// Serve the fake over HTTP on a local port until the returned server is dropped.
+    pub fn serve(&self) -> io::Result<FakeServer> {
+        self.listen("http", |fake, stream| fake.serve_connection(stream))
+    }
+
+    /// Serve the fake over HTTPS on a local port until the returned server is dropped.
+    ///
+    /// `certificate` is the PEM encoded certificate chain of the server, which should be valid
+    /// for `127.0.0.1`, and `key` its private key, PEM encoded in PKCS #8.
+    #[cfg(feature = "native-tls")]
+    pub fn serve_tls(&self, certificate: &[u8], key: &[u8]) -> io::Result<FakeServer> {
+        let identity =
+            native_tls::Identity::from_pkcs8(certificate, key).map_err(io::Error::other)?;
+        let acceptor = native_tls::TlsAcceptor::new(identity).map_err(io::Error::other)?;
+
+        self.listen("https", move |fake, stream| {
+            let stream = acceptor
+                .accept(stream)
+                .map_err(|err| io::Error::other(err.to_string()))?;
+            fake.serve_connection(stream)
+        })
+    }
+
+    /// Serve the fake over HTTPS with rustls, for the tests of builds without `native-tls`.
+    #[cfg(all(
+        test,
+        feature = "rustls-tls",
+        not(feature = "native-tls"),
+        any(feature = "client_api", feature = "hyper-client")
+    ))]
+    pub(crate) fn serve_tls(&self, certificate: &[u8], key: &[u8]) -> io::Result<FakeServer> {
+        let certificates = rustls_pemfile::certs(&mut &*certificate)?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+        let key = rustls_pemfile::pkcs8_private_keys(&mut &*key)?
+            .pop()
+            .ok_or_else(|| io::Error::other("no PKCS #8 private key found"))?;
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certificates, rustls::PrivateKey(key))
+            .map_err(io::Error::other)?;
+        let config = Arc::new(config);
+
+        self.listen("https", move |fake, stream| {
+            let connection =
+                rustls::ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+            fake.serve_connection(rustls::StreamOwned::new(connection, stream))
+        })
+    }
+
+    fn listen<F>(&self, scheme: &'static str, serve: F) -> io::Result<FakeServer>
+    where
+        F: Fn(&FakePorkbun, TcpStream) -> io::Result<()> + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let fake = self.clone();
+        let stopped = stop.clone();
+        let serve = Arc::new(serve);
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let (fake, serve) = (fake.clone(), serve.clone());
+                    thread::spawn(move || {
+                        let _ = serve(&fake, stream);
+                    });
+                }
+            }
+        });
+
+        Ok(FakeServer {
+            scheme,
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    fn serve_connection<S>(&self, stream: S) -> io::Result<()>
+    where
+        S: Read + Write,
+    {
+        let mut reader = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let path = parts.next().unwrap_or_default();
+
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let (status, reply) = match (method.parse::<Method>(), path.strip_prefix(API_PATH)) {
+            (Ok(method), Some(endpoint)) => self.handle(&method, endpoint, &body),
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        };
+        let reply = serde_json::to_vec(&reply)?;
+
+        let stream = reader.get_mut();
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+            status.as_u16(),
+            status.canonical_reason().unwrap_or_default(),
+            reply.len(),
+        )?;
+        stream.write_all(&reply)?;
+        stream.flush()
+    }
+
+    fn respond(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<TestClientError>> {
+        let uri = request.uri_ref().unwrap().to_string();
+        let endpoint = uri.strip_prefix(FAKE_STUB).unwrap_or(&uri);
+        let (status, reply) = self.handle(request.method_ref().unwrap(), endpoint, &body);
+
+        Ok(Response::builder()
+            .status(status)
+            .body(serde_json::to_vec(&reply)?.into())
+            .unwrap())
+    }
+
+    fn credentials(&self) -> Auth {
+        let state = self.state.lock().unwrap();
+        let (apikey, secretapikey) = state
+            .credentials
+            .clone()
+            .unwrap_or_else(|| ("fake-apikey".into(), "fake-secretapikey".into()));
+
+        Auth::new(apikey.into(), secretapikey.into())
+    }
+}
+
+impl State {
+    fn record(&self, domain: &str, id: String, body: &Map<String, Value>) -> Result<Record, Reply> {
+        let record_type = field(body, "type")
+            .map(|t| t.to_uppercase())
+            .filter(|t| RECORD_TYPES.contains(&t.as_str()))
+            .ok_or_else(|| error("Invalid type."))?;
+        let content = field(body, "content")
+            .filter(|content| !content.is_empty())
+            .ok_or_else(|| error("Invalid content."))?;
+        let valid = match record_type.as_str() {
+            "A" => content.parse::<Ipv4Addr>().is_ok(),
+            "AAAA" => content.parse::<Ipv6Addr>().is_ok(),
+            _ => true,
+        };
+        if !valid {
+            return Err(error("Invalid content."));
+        }
+
+        let ttl = match field(body, "ttl") {
+            Some(ttl) => ttl
+                .parse::<u32>()
+                .map_err(|_| error("Invalid TTL."))?
+                .max(DEFAULT_TTL),
+            None => DEFAULT_TTL,
+        };
+        let name = match field(body, "name").map(|name| name.to_lowercase()) {
+            Some(name) if !name.is_empty() => format!("{}.{}", name, domain),
+            _ => domain.into(),
+        };
+        let priority = match record_type.as_str() {
+            "MX" | "SRV" => field(body, "prio").unwrap_or_else(|| "0".into()),
+            _ => "0".into(),
+        };
+
+        Ok(Record {
+            id,
+            name,
+            record_type,
+            content,
+            ttl: ttl.to_string(),
+            priority: Some(priority),
+            notes: Some(String::new()),
+        })
+    }
+
+    fn create(&mut self, domain: &str, body: &Map<String, Value>) -> Reply {
+        if !self.domains.contains_key(domain) {
+            return error("Invalid domain.");
+        }
+
+        let id = self.next_id;
+        let record = match self.record(domain, id.to_string(), body) {
+            Ok(record) => record,
+            Err(reply) => return reply,
+        };
+        let records = self.domains.get_mut(domain).unwrap();
+        let duplicate = records.iter().any(|existing| {
+            existing.name == record.name
+                && existing.record_type == record.record_type
+                && existing.content == record.content
+        });
+        if duplicate {
+            return error("Create error: Duplicate record.");
+        }
+
+        self.next_id += 1;
+        records.push(record);
+        success(json!({ "id": id }))
+    }
+
+    fn edit(&mut self, domain: &str, id: &str, body: &Map<String, Value>) -> Reply {
+        if !self.domains.contains_key(domain) {
+            return error("Invalid domain.");
+        }
+
+        let record = match self.record(domain, id.into(), body) {
+            Ok(record) => record,
+            Err(reply) => return reply,
+        };
+        let records = self.domains.get_mut(domain).unwrap();
+        match records.iter_mut().find(|existing| existing.id == id) {
+            Some(existing) => {
+                *existing = record;
+                success(json!({}))
+            }
+            None => error("Edit error: We were unable to edit the DNS record."),
+        }
+    }
+
+    fn delete(&mut self, domain: &str, id: &str) -> Reply {
+        let records = match self.domains.get_mut(domain) {
+            Some(records) => records,
+            None => return error("Invalid domain."),
+        };
+
+        let before = records.len();
+        records.retain(|record| record.id != id);
+        if records.len() == before {
+            return error("Delete error: Invalid record id.");
+        }
+
+        success(json!({}))
+    }
+
+    fn list_all(&self, body: &Map<String, Value>) -> Reply {
+        let start = match field(body, "start").map(|start| start.parse::<usize>()) {
+            None => 0,
+            Some(Ok(start)) => start,
+            Some(Err(_)) => return error("Invalid start."),
+        };
+
+        let domains = self
+            .domains
+            .keys()
+            .skip(start)
+            .take(LIST_ALL_PAGE_SIZE)
+            .map(|domain| {
+                json!({
+                    "domain": domain,
+                    "status": "ACTIVE",
+                    "tld": domain.rsplit('.').next(),
+                    "createDate": "2021-01-01 00:00:00",
+                    "expireDate": "2031-01-01 00:00:00",
+                    "securityLock": "1",
+                    "whoisPrivacy": "1",
+                    "autoRenew": 0,
+                    "notLocal": 0,
+                })
+            })
+            .collect::<Vec<_>>();
+        success(json!({ "domains": domains }))
+    }
+
+    fn retrieve(&self, domain: &str, id: Option<&str>) -> Reply {
+        let records = match self.domains.get(domain) {
+            Some(records) => records,
+            None => return error("Invalid domain."),
+        };
+
+        let records = records
+            .iter()
+            .filter(|record| id.is_none_or(|id| record.id == id))
+            .collect::<Vec<_>>();
+        success(json!({ "records": records }))
+    }
+}
+
+impl Client for FakePorkbun {
+    type Error = TestClientError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        Ok(Url::parse(FAKE_STUB)?.join(endpoint)?)
+    }
+
+    fn rest(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        self.respond(request, body)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(self.credentials())
+    }
+}
+
+#[async_trait]
+impl AsyncClient for FakePorkbun {
+    type Error = TestClientError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        <Self as Client>::rest_endpoint(self, endpoint)
+    }
+
+    async fn rest_async(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<Response<Bytes>, ApiError<Self::Error>> {
+        self.respond(request, body)
+    }
+
+    fn auth(&self) -> Option<Auth> {
+        Some(self.credentials())
+    }
+}
+
+/// A [`FakePorkbun`] served over HTTP or HTTPS.
+///
+/// The server stops when this is dropped.
+#[derive(Debug)]
+pub struct FakeServer {
+    scheme: &'static str,
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FakeServer {
+    /// The API root to point clients at.
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("{}://{}{}", self.scheme, self.addr, API_PATH)).expect("a valid URL")
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the listener up so that it notices it should stop.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "client_api"))]
+mod tests {
+    use http::Method;
+    use serde_json::json;
+
+    use crate::{
+        api::{self, ApiError, AsyncQuery, Query},
+        auth::Auth,
+        endpoints::{CreateDns, DeleteDns, DnsContent, EditDns, ListAllDomains, Ping, RetrieveDns},
+        AsyncPorkbun, CreateResponse, ListAllResponse, PingResponse, Porkbun, RetrieveResponse,
+    };
+
+    use super::FakePorkbun;
+
+    fn create(content: &str) -> CreateDns<'static> {
+        CreateDns::builder()
+            .domain("example.com")
+            .name("www")
+            .record(DnsContent::A {
+                content: content.parse().unwrap(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn retrieve() -> RetrieveDns<'static> {
+        RetrieveDns::builder()
+            .domain("example.com")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn records_lifecycle() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+
+        let created: CreateResponse = create("10.0.0.1").query(&fake).unwrap();
+        let edit = EditDns::builder()
+            .domain("example.com")
+            .id(created.id.clone())
+            .name("www")
+            .ttl(3600)
+            .record(DnsContent::A {
+                content: "10.0.0.2".parse().unwrap(),
+            })
+            .build()
+            .unwrap();
+        api::ignore(edit).query(&fake).unwrap();
+
+        let rsp: RetrieveResponse = retrieve().query(&fake).unwrap();
+        assert_eq!(rsp.records.len(), 1);
+        assert_eq!(rsp.records[0].id, created.id);
+        assert_eq!(rsp.records[0].name, "www.example.com");
+        assert_eq!(rsp.records[0].record_type, "A");
+        assert_eq!(rsp.records[0].content, "10.0.0.2");
+        assert_eq!(rsp.records[0].ttl, "3600");
+
+        let delete = DeleteDns::builder()
+            .domain("example.com")
+            .id(created.id)
+            .build()
+            .unwrap();
+        api::ignore(delete).query(&fake).unwrap();
+        assert!(fake.records("example.com").unwrap().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+
+        let unknown = RetrieveDns::builder()
+            .domain("example.org")
+            .build()
+            .unwrap();
+        let err = api::ignore(unknown).query(&fake).unwrap_err();
+        if let ApiError::PorkBun { message, .. } = err {
+            assert_eq!(message, "Invalid domain.");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+
+        let delete = DeleteDns::builder()
+            .domain("example.com")
+            .id("1")
+            .build()
+            .unwrap();
+        let err = api::ignore(delete).query(&fake).unwrap_err();
+        if let ApiError::PorkBun { message, .. } = err {
+            assert_eq!(message, "Delete error: Invalid record id.");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+
+        let (status, reply) = fake.handle(&Method::GET, "ping", b"{}");
+        assert_eq!(status.as_u16(), 400);
+        assert_eq!(
+            reply,
+            json!({"status": "ERROR", "message": "All HTTP request must use POST."}),
+        );
+    }
+
+    #[test]
+    fn list_all() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.org")
+            .with_domain("example.com");
+
+        let rsp: ListAllResponse = ListAllDomains::builder()
+            .build()
+            .unwrap()
+            .query(&fake)
+            .unwrap();
+        let domains = rsp.domains.iter().map(|domain| domain.domain.as_str());
+        assert_eq!(domains.collect::<Vec<_>>(), ["example.com", "example.org"]);
+
+        let rsp: ListAllResponse = ListAllDomains::builder()
+            .start(2)
+            .build()
+            .unwrap()
+            .query(&fake)
+            .unwrap();
+        assert!(rsp.domains.is_empty());
+    }
+
+    #[test]
+    fn credentials() {
+        let fake = FakePorkbun::new().with_credentials("pk1", "sk1");
+        let (_, reply) = fake.handle(
+            &Method::POST,
+            "ping",
+            br#"{"apikey": "pk1", "secretapikey": "wrong"}"#,
+        );
+        assert_eq!(reply["message"], "Invalid API key. (002)");
+
+        let rsp: PingResponse = Ping::builder().build().unwrap().query(&fake).unwrap();
+        assert_eq!(rsp.your_ip, "127.0.0.1");
+    }
+
+    #[test]
+    fn serve_blocking() {
+        let fake = FakePorkbun::new()
+            .with_domain("example.com")
+            .with_credentials("pk1", "sk1")
+            .with_your_ip("192.0.2.1");
+        let server = fake.serve().unwrap();
+        let client = Porkbun::new(Auth::new("pk1".into(), "sk1".into()))
+            .unwrap()
+            .with_url(server.url());
+
+        let rsp: PingResponse = Ping::builder().build().unwrap().query(&client).unwrap();
+        assert_eq!(rsp.your_ip, "192.0.2.1");
+
+        let created: CreateResponse = create("10.0.0.1").query(&client).unwrap();
+        let records = fake.records("example.com").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, created.id);
+    }
+
+    #[tokio::test]
+    async fn serve_async() {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let server = fake.serve().unwrap();
+        let client = AsyncPorkbun::new(Auth::new("pk1".into(), "sk1".into()))
+            .unwrap()
+            .with_url(server.url());
+
+        let _: CreateResponse = create("10.0.0.1").query_async(&client).await.unwrap();
+        let err = api::ignore(create("10.0.0.1"))
+            .query_async(&client)
+            .await
+            .unwrap_err();
+        if let ApiError::PorkBun { message, .. } = err {
+            assert_eq!(message, "Create error: Duplicate record.");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+
+        let rsp: RetrieveResponse = retrieve().query_async(&client).await.unwrap();
+        assert_eq!(rsp.records.len(), 1);
+    }
+}
@@ -1 +1,24 @@
-pub(crate) mod client;
+//! Clients for testing code which talks to Porkbun without touching the network.
+//!
+//! This module is available with the `test-util` feature.
+
+pub mod cassette;
+pub mod client;
+pub mod fake;
+#[cfg(all(
+    test,
+    any(
+        all(
+            any(feature = "native-tls", feature = "rustls-tls"),
+            any(feature = "client_api", feature = "hyper-client")
+        ),
+        all(feature = "native-tls", feature = "ureq-client")
+    )
+))]
+pub(crate) mod tls;
+
+pub use self::cassette::{Cassette, CassetteError, RecordingClient, ReplayClient, ReplayError};
+pub use self::client::{
+    ExpectedUrl, ExpectedUrlBuilder, SingleTestClient, TestClient, TestClientError,
+};
+pub use self::fake::{FakePorkbun, FakeServer};
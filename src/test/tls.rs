@@ -0,0 +1,36 @@
+//! Certificates for serving fakes over HTTPS in tests.
+
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa, SanType};
+
+/// A certificate authority and a certificate it signed for `127.0.0.1`, all PEM encoded.
+pub(crate) struct TestCertificates {
+    /// The certificate of the authority.
+    pub(crate) ca: String,
+    /// The certificate of the server.
+    pub(crate) certificate: String,
+    /// The private key of the server, in PKCS #8.
+    pub(crate) key: String,
+}
+
+impl TestCertificates {
+    pub(crate) fn new() -> Self {
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "porkbun-rs test CA");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(params).unwrap();
+
+        let mut params = CertificateParams::new(vec!["localhost".into()]);
+        params
+            .subject_alt_names
+            .push(SanType::IpAddress([127, 0, 0, 1].into()));
+        let server = Certificate::from_params(params).unwrap();
+
+        Self {
+            ca: ca.serialize_pem().unwrap(),
+            certificate: server.serialize_pem_with_signer(&ca).unwrap(),
+            key: server.serialize_private_key_pem(),
+        }
+    }
+}
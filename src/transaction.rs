@@ -0,0 +1,502 @@
+//! Change several records of a domain together.
+//!
+//! A [`Transaction`] is a sequence of creations, edits and deletions. The records it edits or
+//! deletes are retrieved first; if an operation fails, the operations already made are undone
+//! in reverse order, so that the domain is left as it was:
+//!
+//! - created records are deleted;
+//! - edited records are edited back;
+//! - deleted records are created again, with a new id, which the operations undone after
+//!   that use instead of the old one.
+
+use log::warn;
+use std::{collections::HashMap, fmt};
+use thiserror::Error;
+
+use crate::{
+    api::{ApiError, Client, Query},
+    endpoints::{DeleteDns, DnsContentError, RetrieveDns},
+    sync::{Change, DesiredRecord, Plan},
+    CreateResponse, DeleteResponse, EditResponse, RetrieveResponse,
+};
+
+/// An operation on the records of a domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Create a record.
+    Create {
+        /// The record.
+        record: DesiredRecord,
+    },
+    /// Replace a record.
+    Edit {
+        /// The id of the record.
+        id: String,
+        /// The record it becomes.
+        record: DesiredRecord,
+    },
+    /// Delete a record.
+    Delete {
+        /// The id of the record.
+        id: String,
+    },
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Create { record } => write!(f, "create {}", describe(record)),
+            Operation::Edit { id, record } => write!(f, "edit {} to {}", id, describe(record)),
+            Operation::Delete { id } => write!(f, "delete {}", id),
+        }
+    }
+}
+
+fn describe(record: &DesiredRecord) -> String {
+    format!(
+        "{} {} {}",
+        record.name.as_deref().unwrap_or("@"),
+        record.record.record_type(),
+        record.record.content()
+    )
+}
+
+/// An operation undoing part of a failed transaction which failed as well.
+#[derive(Debug)]
+pub struct RollbackFailure<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// The operation undoing an operation of the transaction.
+    pub operation: Operation,
+    /// The source of the error.
+    pub source: ApiError<E>,
+}
+
+/// Errors which may occur while committing a transaction.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TransactionError<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    /// The records of the domain could not be retrieved; nothing was changed.
+    #[error("failed to retrieve the records of {}: {}", domain, source)]
+    Snapshot {
+        /// The domain.
+        domain: String,
+        /// The source of the error.
+        source: ApiError<E>,
+    },
+    /// A record to edit or delete does not exist; nothing was changed.
+    #[error("there is no record {} in {}", id, domain)]
+    MissingRecord {
+        /// The domain.
+        domain: String,
+        /// The id of the record.
+        id: String,
+    },
+    /// A record to edit or delete could not be created again; nothing was changed.
+    #[error("record {} cannot be restored: {}", id, source)]
+    Unsupported {
+        /// The id of the record.
+        id: String,
+        /// The source of the error.
+        source: DnsContentError,
+    },
+    /// An operation failed, and the operations made before it were undone.
+    #[error(
+        "failed to {}: {}{}",
+        operation,
+        source,
+        if rollback.is_empty() {
+            String::new()
+        } else {
+            format!(" ({} operations could not be undone)", rollback.len())
+        }
+    )]
+    Failed {
+        /// The operation which failed.
+        operation: Box<Operation>,
+        /// The source of the error.
+        source: ApiError<E>,
+        /// The operations undoing the transaction which failed as well.
+        rollback: Vec<RollbackFailure<E>>,
+    },
+}
+
+/// An operation undoing one made by a transaction.
+struct Undo {
+    operation: Operation,
+    /// The id of the record it creates again, if it undoes a deletion.
+    restores: Option<String>,
+}
+
+/// Operations on the records of a domain, made all together or not at all.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    domain: String,
+    operations: Vec<Operation>,
+}
+
+impl Transaction {
+    /// A transaction without any operation on `domain`.
+    pub fn new<S>(domain: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            domain: domain.into().trim_end_matches('.').to_lowercase(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Create a record.
+    pub fn create(mut self, record: DesiredRecord) -> Self {
+        self.operations.push(Operation::Create { record });
+        self
+    }
+
+    /// Replace the record `id`.
+    pub fn edit<S>(mut self, id: S, record: DesiredRecord) -> Self
+    where
+        S: Into<String>,
+    {
+        self.operations.push(Operation::Edit {
+            id: id.into(),
+            record,
+        });
+        self
+    }
+
+    /// Delete the record `id`.
+    pub fn delete<S>(mut self, id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.operations.push(Operation::Delete { id: id.into() });
+        self
+    }
+
+    /// The operations, in the order they are made.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Make the operations, undoing them all if one fails.
+    ///
+    /// Returns the ids of the created records, in the order of their operations.
+    pub fn commit<C>(&self, client: &C) -> Result<Vec<String>, TransactionError<C::Error>>
+    where
+        C: Client,
+    {
+        // The records as they are before each operation.
+        let mut current = self.snapshot(client)?;
+
+        let mut undo = Vec::new();
+        let mut created = Vec::new();
+        for operation in &self.operations {
+            match self.make(client, operation) {
+                Ok(id) => {
+                    let compensation = match operation {
+                        Operation::Create { .. } => Undo {
+                            operation: Operation::Delete { id: id.clone() },
+                            restores: None,
+                        },
+                        Operation::Edit { id, record } => Undo {
+                            operation: Operation::Edit {
+                                id: id.clone(),
+                                record: current.insert(id.clone(), record.clone()).unwrap(),
+                            },
+                            restores: None,
+                        },
+                        Operation::Delete { id } => Undo {
+                            operation: Operation::Create {
+                                record: current[id].clone(),
+                            },
+                            restores: Some(id.clone()),
+                        },
+                    };
+                    undo.push(compensation);
+                    if let Operation::Create { .. } = operation {
+                        created.push(id);
+                    }
+                }
+                Err(source) => {
+                    return Err(TransactionError::Failed {
+                        operation: Box::new(operation.clone()),
+                        source,
+                        rollback: self.rollback(client, undo),
+                    })
+                }
+            }
+        }
+
+        Ok(created)
+    }
+
+    /// The records the operations edit or delete, as they are.
+    fn snapshot<C>(
+        &self,
+        client: &C,
+    ) -> Result<HashMap<String, DesiredRecord>, TransactionError<C::Error>>
+    where
+        C: Client,
+    {
+        let rsp: RetrieveResponse = RetrieveDns::builder()
+            .domain(self.domain.as_str())
+            .build()
+            .map_err(ApiError::endpoint)
+            .and_then(|endpoint| endpoint.query(client))
+            .map_err(|source| TransactionError::Snapshot {
+                domain: self.domain.clone(),
+                source,
+            })?;
+
+        let mut snapshot = HashMap::new();
+        for operation in &self.operations {
+            let id = match operation {
+                Operation::Edit { id, .. } | Operation::Delete { id } => id,
+                Operation::Create { .. } => continue,
+            };
+            let record = rsp
+                .records
+                .iter()
+                .find(|record| &record.id == id)
+                .ok_or_else(|| TransactionError::MissingRecord {
+                    domain: self.domain.clone(),
+                    id: id.clone(),
+                })?;
+            let record = DesiredRecord::from_record(&self.domain, record).map_err(|source| {
+                TransactionError::Unsupported {
+                    id: id.clone(),
+                    source,
+                }
+            })?;
+            snapshot.insert(id.clone(), record);
+        }
+        Ok(snapshot)
+    }
+
+    /// Make an operation, returning the id of the record.
+    fn make<C>(&self, client: &C, operation: &Operation) -> Result<String, ApiError<C::Error>>
+    where
+        C: Client,
+    {
+        let domain = self.domain.as_str();
+        match operation {
+            Operation::Create { record } => {
+                let rsp: CreateResponse = record
+                    .create(domain)
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+                Ok(rsp.id)
+            }
+            Operation::Edit { id, record } => {
+                let _: EditResponse = record
+                    .edit(domain, id)
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+                Ok(id.clone())
+            }
+            Operation::Delete { id } => {
+                let _: DeleteResponse = DeleteDns::builder()
+                    .domain(domain)
+                    .id(id.as_str())
+                    .build()
+                    .map_err(ApiError::endpoint)?
+                    .query(client)?;
+                Ok(id.clone())
+            }
+        }
+    }
+
+    /// Make the compensating operations in reverse order, carrying on after failures.
+    ///
+    /// Records deleted by the transaction get a new id when they are created again, so the
+    /// operations undone before their deletion are pointed at the new id.
+    fn rollback<C>(&self, client: &C, undo: Vec<Undo>) -> Vec<RollbackFailure<C::Error>>
+    where
+        C: Client,
+    {
+        let mut ids = HashMap::new();
+        undo.into_iter()
+            .rev()
+            .filter_map(
+                |Undo {
+                     operation,
+                     restores,
+                 }| {
+                    let operation = match operation {
+                        Operation::Edit { id, record } => Operation::Edit {
+                            id: ids.get(&id).cloned().unwrap_or(id),
+                            record,
+                        },
+                        Operation::Delete { id } => Operation::Delete {
+                            id: ids.get(&id).cloned().unwrap_or(id),
+                        },
+                        operation => operation,
+                    };
+                    match self.make(client, &operation) {
+                        Ok(id) => {
+                            if let Some(old) = restores {
+                                ids.insert(old, id);
+                            }
+                            None
+                        }
+                        Err(source) => {
+                            warn!(
+                                target: "porkbun",
+                                "failed to roll back {} with `{}`: {}",
+                                self.domain, operation, source
+                            );
+                            Some(RollbackFailure { operation, source })
+                        }
+                    }
+                },
+            )
+            .collect()
+    }
+}
+
+impl From<&Plan> for Transaction {
+    fn from(plan: &Plan) -> Self {
+        let operations = plan.changes.iter().map(|change| match change {
+            Change::Delete { current } => Operation::Delete {
+                id: current.id.clone(),
+            },
+            Change::Edit { current, desired } => Operation::Edit {
+                id: current.id.clone(),
+                record: desired.clone(),
+            },
+            Change::Create { desired } => Operation::Create {
+                record: desired.clone(),
+            },
+        });
+        Self {
+            domain: plan.domain.clone(),
+            operations: operations.collect(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sync", feature = "async"))]
+mod tests {
+    use crate::{endpoints::DnsContent, sync::DesiredRecord, test::FakePorkbun, Record};
+
+    use super::{Transaction, TransactionError};
+
+    fn mx(content: &str, priority: u16) -> DesiredRecord {
+        DesiredRecord::new(DnsContent::Mx {
+            content: content.into(),
+            priority,
+        })
+    }
+
+    fn summary(records: &[Record]) -> Vec<(String, String, String)> {
+        let mut summary = records
+            .iter()
+            .map(|record| {
+                (
+                    record.record_type.clone(),
+                    record.content.clone(),
+                    record.priority.clone().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        summary.sort();
+        summary
+    }
+
+    fn fake() -> (FakePorkbun, Vec<String>) {
+        let fake = FakePorkbun::new().with_domain("example.com");
+        let ids = Transaction::new("example.com")
+            .create(mx("mail1.example.com", 10))
+            .create(mx("mail2.example.com", 20))
+            .commit(&fake)
+            .unwrap();
+        (fake, ids)
+    }
+
+    #[test]
+    fn commit() {
+        let (fake, ids) = fake();
+        assert_eq!(ids, ["250000000", "250000001"]);
+
+        let created = Transaction::new("example.com")
+            .delete(&ids[0])
+            .edit(&ids[1], mx("mx.example.net", 10))
+            .create(mx("mx2.example.net", 20))
+            .commit(&fake)
+            .unwrap();
+        assert_eq!(created, ["250000002"]);
+        assert_eq!(
+            summary(&fake.records("example.com").unwrap()),
+            [
+                ("MX".into(), "mx.example.net".into(), "10".into()),
+                ("MX".into(), "mx2.example.net".into(), "20".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rollback() {
+        let (fake, ids) = fake();
+        let before = summary(&fake.records("example.com").unwrap());
+
+        // The last creation duplicates the record edited just before.
+        let err = Transaction::new("example.com")
+            .delete(&ids[0])
+            .edit(&ids[1], mx("mx.example.net", 10))
+            .create(mx("mx2.example.net", 20))
+            .create(mx("mx.example.net", 10))
+            .commit(&fake)
+            .unwrap_err();
+        match err {
+            TransactionError::Failed {
+                operation,
+                rollback,
+                ..
+            } => {
+                assert_eq!(operation.to_string(), "create @ MX mx.example.net");
+                assert!(rollback.is_empty());
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        assert_eq!(summary(&fake.records("example.com").unwrap()), before);
+    }
+
+    #[test]
+    fn rollback_recreated_record() {
+        let (fake, ids) = fake();
+        let before = summary(&fake.records("example.com").unwrap());
+
+        // Undoing the edit has to target the record created again in place of the deleted one.
+        let err = Transaction::new("example.com")
+            .edit(&ids[0], mx("mx.example.net", 10))
+            .delete(&ids[0])
+            .create(mx("mail2.example.com", 20))
+            .commit(&fake)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to create @ MX mail2.example.com: porkbun server error: \
+             Create error: Duplicate record. status: ERROR"
+        );
+        if let TransactionError::Failed { rollback, .. } = err {
+            assert!(rollback.is_empty());
+        }
+        assert_eq!(summary(&fake.records("example.com").unwrap()), before);
+    }
+
+    #[test]
+    fn missing_record() {
+        let (fake, _) = fake();
+
+        let err = Transaction::new("example.com")
+            .delete("1")
+            .commit(&fake)
+            .unwrap_err();
+        assert!(matches!(err, TransactionError::MissingRecord { .. }));
+    }
+}
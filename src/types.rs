@@ -1,54 +1,145 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::name::{DomainName, NameError, RecordName};
+
+/// Accept identifiers sent either as strings or as numbers.
+fn string_or_number<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        String(String),
+        Number(u64),
+    }
+
+    Ok(match Id::deserialize(deserializer)? {
+        Id::String(id) => id,
+        Id::Number(id) => id.to_string(),
+    })
+}
+
+/// The status Porkbun reports in every response.
+///
+/// Responses are checked for errors while they are decoded, so the response types no longer
+/// carry it.
+#[deprecated(note = "errors reported by Porkbun are returned as `ApiError::PorkBun`")]
 #[derive(Debug, Deserialize, Clone)]
 pub struct Status {
-    status: String,
-    message: Option<String>,
+    pub status: String,
+    pub message: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Record {
-    id: String,
-    name: String,
+    pub id: String,
+    pub name: String,
     #[serde(rename = "type")]
-    record_type: String,
-    content: String,
-    ttl: String,
+    pub record_type: String,
+    pub content: String,
+    pub ttl: String,
     #[serde(rename = "prio")]
-    priority: Option<String>,
-    notes: Option<String>,
+    pub priority: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Record {
+    /// The name of the record relative to `zone`, the domain it was retrieved from.
+    pub fn relative_name(&self, zone: &DomainName) -> Result<RecordName, NameError> {
+        RecordName::from_fqdn(&self.name, zone)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct PingResponse {
     #[serde(rename = "yourIp")]
-    your_ip: String,
-    #[serde(flatten)]
-    status: Status,
+    pub your_ip: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct CreateResponse {
-    id: String,
-    #[serde(flatten)]
-    status: Status,
+    #[serde(deserialize_with = "string_or_number")]
+    pub id: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
-pub struct EditResponse {
-    #[serde(flatten)]
-    status: Status,
-}
+pub struct EditResponse {}
 
 #[derive(Debug, Deserialize, Clone)]
-pub struct DeleteResponse {
-    #[serde(flatten)]
-    status: Status,
-}
+pub struct DeleteResponse {}
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct RetrieveResponse {
-    records: Vec<Record>,
-    #[serde(flatten)]
-    status: Status,
+    pub records: Vec<Record>,
+}
+
+/// A domain of the account.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Domain {
+    pub domain: String,
+    pub status: Option<String>,
+    pub tld: Option<String>,
+    #[serde(rename = "createDate")]
+    pub create_date: Option<String>,
+    #[serde(rename = "expireDate")]
+    pub expire_date: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+/// A label attached to a domain.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Label {
+    #[serde(deserialize_with = "string_or_number")]
+    pub id: String,
+    pub title: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ListAllResponse {
+    #[serde(default)]
+    pub domains: Vec<Domain>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{CreateResponse, ListAllResponse};
+
+    #[test]
+    fn create_response_numeric_id() {
+        let rsp: CreateResponse = serde_json::from_value(json!({"id": 106926659})).unwrap();
+        assert_eq!(rsp.id, "106926659");
+    }
+
+    #[test]
+    fn create_response_string_id() {
+        let rsp: CreateResponse = serde_json::from_value(json!({"id": "106926659"})).unwrap();
+        assert_eq!(rsp.id, "106926659");
+    }
+
+    #[test]
+    fn list_all_response() {
+        let rsp: ListAllResponse = serde_json::from_value(json!({
+            "status": "SUCCESS",
+            "domains": [{
+                "domain": "example.com",
+                "status": "ACTIVE",
+                "tld": "com",
+                "createDate": "2018-08-20 17:52:51",
+                "expireDate": "2023-08-20 17:52:51",
+                "securityLock": "1",
+                "whoisPrivacy": "1",
+                "autoRenew": 0,
+                "notLocal": 0,
+                "labels": [{ "id": 27240, "title": "cool", "color": "#ff0000" }],
+            }],
+        }))
+        .unwrap();
+        assert_eq!(rsp.domains[0].domain, "example.com");
+        assert_eq!(rsp.domains[0].labels[0].id, "27240");
+    }
 }
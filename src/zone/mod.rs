@@ -0,0 +1,7 @@
+//! Zone files in the RFC 1035 master file format.
+
+mod parse;
+mod render;
+
+pub use self::parse::{parse, Import, ParseError, Warning};
+pub use self::render::render;
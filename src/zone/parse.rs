@@ -0,0 +1,780 @@
+use log::warn;
+use std::{
+    fmt::{self, Display},
+    net::{Ipv4Addr, Ipv6Addr},
+};
+use thiserror::Error;
+
+use crate::endpoints::{CreateDns, DnsContent};
+
+/// Errors which may occur while parsing a zone file.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// A quoted string is not closed.
+    #[error("line {}: unterminated string", line)]
+    UnterminatedString {
+        /// The line the string starts on.
+        line: usize,
+    },
+    /// A parenthesis is not closed, or closed without being opened.
+    #[error("line {}: unbalanced parentheses", line)]
+    Parentheses {
+        /// The line of the parenthesis.
+        line: usize,
+    },
+    /// A directive other than `$ORIGIN` and `$TTL`.
+    #[error("line {}: unsupported directive `{}`", line, directive)]
+    Directive {
+        /// The line of the directive.
+        line: usize,
+        /// The directive.
+        directive: String,
+    },
+    /// A TTL is not a number of seconds, optionally with units.
+    #[error("line {}: invalid TTL `{}`", line, ttl)]
+    Ttl {
+        /// The line of the TTL.
+        line: usize,
+        /// The TTL.
+        ttl: String,
+    },
+    /// A class other than `IN`.
+    #[error("line {}: unsupported class `{}`", line, class)]
+    Class {
+        /// The line of the record.
+        line: usize,
+        /// The class.
+        class: String,
+    },
+    /// The first record has no owner name.
+    #[error("line {}: missing owner name", line)]
+    MissingOwner {
+        /// The line of the record.
+        line: usize,
+    },
+    /// A record has no type.
+    #[error("line {}: missing record type", line)]
+    MissingType {
+        /// The line of the record.
+        line: usize,
+    },
+    /// The owner name of a record or the domain is not valid.
+    #[error("line {}: invalid name: {}", line, message)]
+    Name {
+        /// The line of the record.
+        line: usize,
+        /// What is wrong.
+        message: String,
+    },
+    /// The data of a record is not valid for its type.
+    #[error("line {}: invalid {} record: {}", line, record_type, message)]
+    Data {
+        /// The line of the record.
+        line: usize,
+        /// The type of the record.
+        record_type: String,
+        /// What is wrong.
+        message: String,
+    },
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// A record which was skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The SOA record, which Porkbun manages.
+    Soa {
+        /// The line of the record.
+        line: usize,
+    },
+    /// An NS record for the domain itself, which Porkbun manages.
+    ApexNs {
+        /// The line of the record.
+        line: usize,
+    },
+    /// A record of a type Porkbun records cannot be created with.
+    Unsupported {
+        /// The line of the record.
+        line: usize,
+        /// The type of the record.
+        record_type: String,
+    },
+    /// A record outside of the domain.
+    OutOfZone {
+        /// The line of the record.
+        line: usize,
+        /// The name of the record.
+        name: String,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Soa { line } => write!(f, "line {}: skipped SOA record", line),
+            Warning::ApexNs { line } => write!(f, "line {}: skipped apex NS record", line),
+            Warning::Unsupported { line, record_type } => {
+                write!(
+                    f,
+                    "line {}: unsupported record type `{}`",
+                    line, record_type
+                )
+            }
+            Warning::OutOfZone { line, name } => {
+                write!(f, "line {}: `{}` is outside of the domain", line, name)
+            }
+        }
+    }
+}
+
+/// The records read from a zone file.
+#[derive(Debug)]
+pub struct Import {
+    /// The records to create.
+    pub records: Vec<CreateDns<'static>>,
+    /// The records which were skipped.
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+/// The tokens of a record or directive, which may span lines within parentheses.
+#[derive(Debug)]
+struct Entry {
+    line: usize,
+    /// Whether the entry starts with blanks, which stand for the previous owner name.
+    indented: bool,
+    tokens: Vec<Token>,
+}
+
+fn entries(input: &str) -> ParseResult<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+    let mut depth = 0;
+    let mut paren_line = 0;
+    let mut entry = Entry {
+        line,
+        indented: matches!(chars.peek(), Some(' ') | Some('\t')),
+        tokens: Vec::new(),
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                if depth == 0 {
+                    let indented = matches!(chars.peek(), Some(' ') | Some('\t'));
+                    let next = Entry {
+                        line,
+                        indented,
+                        tokens: Vec::new(),
+                    };
+                    let done = std::mem::replace(&mut entry, next);
+                    if !done.tokens.is_empty() {
+                        entries.push(done);
+                    }
+                }
+            }
+            ' ' | '\t' | '\r' => {}
+            ';' => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '(' => {
+                if depth == 0 {
+                    paren_line = line;
+                }
+                depth += 1;
+            }
+            ')' => {
+                if depth == 0 {
+                    return Err(ParseError::Parentheses { line });
+                }
+                depth -= 1;
+            }
+            '"' => {
+                let start = line;
+                let mut bytes = Vec::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => escape(&mut chars, &mut bytes),
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            push_char(&mut bytes, c);
+                        }
+                        None => return Err(ParseError::UnterminatedString { line: start }),
+                    }
+                }
+                entry.tokens.push(Token {
+                    text: String::from_utf8_lossy(&bytes).into_owned(),
+                    quoted: true,
+                });
+            }
+            c => {
+                let mut bytes = Vec::new();
+                let mut c = c;
+                loop {
+                    if c == '\\' {
+                        escape(&mut chars, &mut bytes);
+                    } else {
+                        push_char(&mut bytes, c);
+                    }
+                    match chars.peek() {
+                        Some(&next) if !" \t\r\n;()\"".contains(next) => {
+                            c = next;
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                entry.tokens.push(Token {
+                    text: String::from_utf8_lossy(&bytes).into_owned(),
+                    quoted: false,
+                });
+            }
+        }
+    }
+
+    if depth != 0 {
+        return Err(ParseError::Parentheses { line: paren_line });
+    }
+    if !entry.tokens.is_empty() {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn push_char(bytes: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// Read an escape, either `\DDD` for a byte or `\X` for `X`.
+fn escape<I>(chars: &mut std::iter::Peekable<I>, bytes: &mut Vec<u8>)
+where
+    I: Iterator<Item = char> + Clone,
+{
+    let digits = chars.clone().take(3).collect::<String>();
+    if digits.len() == 3 && digits.chars().all(|c| c.is_ascii_digit()) {
+        if let Ok(byte) = digits.parse::<u8>() {
+            bytes.push(byte);
+            chars.nth(2);
+            return;
+        }
+    }
+    if let Some(c) = chars.next() {
+        push_char(bytes, c);
+    }
+}
+
+/// Parse a TTL in seconds, or with `w`, `d`, `h`, `m` and `s` units such as `1h30m`.
+fn ttl(text: &str) -> Option<u32> {
+    if let Ok(ttl) = text.parse() {
+        return Some(ttl);
+    }
+
+    let mut total = 0u32;
+    let mut digits = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            'w' => 604_800,
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let value = digits.parse::<u32>().ok()?;
+        total = total.checked_add(value.checked_mul(unit)?)?;
+        digits.clear();
+    }
+    if digits.is_empty() && !text.is_empty() {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+struct Parser {
+    domain: String,
+    origin: String,
+    default_ttl: Option<u32>,
+    last_ttl: Option<u32>,
+    last_owner: Option<String>,
+    records: Vec<CreateDns<'static>>,
+    warnings: Vec<Warning>,
+}
+
+impl Parser {
+    /// Make a name absolute, without the trailing dot.
+    fn absolute(&self, name: &str) -> String {
+        let name = name.to_lowercase();
+        if name == "@" {
+            self.origin.clone()
+        } else if let Some(name) = name.strip_suffix('.') {
+            name.into()
+        } else {
+            format!("{}.{}", name, self.origin)
+        }
+    }
+
+    /// The name relative to the domain, `None` for the domain itself, or `Err` outside of it.
+    fn relative(&self, name: &str) -> Result<Option<String>, ()> {
+        if name == self.domain {
+            Ok(None)
+        } else {
+            name.strip_suffix(&self.domain)
+                .and_then(|name| name.strip_suffix('.'))
+                .map(|name| Some(name.into()))
+                .ok_or(())
+        }
+    }
+
+    fn directive(&mut self, entry: &Entry) -> ParseResult<()> {
+        let directive = entry.tokens[0].text.to_uppercase();
+        let argument = entry.tokens.get(1).map(|token| token.text.as_str());
+        match (directive.as_str(), argument) {
+            ("$ORIGIN", Some(origin)) => {
+                self.origin = self.absolute(origin);
+                Ok(())
+            }
+            ("$TTL", Some(text)) => {
+                self.default_ttl = Some(ttl(text).ok_or_else(|| ParseError::Ttl {
+                    line: entry.line,
+                    ttl: text.into(),
+                })?);
+                Ok(())
+            }
+            _ => Err(ParseError::Directive {
+                line: entry.line,
+                directive: entry.tokens[0].text.clone(),
+            }),
+        }
+    }
+
+    fn record(&mut self, entry: &Entry) -> ParseResult<()> {
+        let line = entry.line;
+        let mut tokens = entry.tokens.iter().peekable();
+
+        let owner = if entry.indented {
+            self.last_owner
+                .clone()
+                .ok_or(ParseError::MissingOwner { line })?
+        } else {
+            let owner = self.absolute(&tokens.next().unwrap().text);
+            self.last_owner = Some(owner.clone());
+            owner
+        };
+
+        // The TTL and the class may come in either order.
+        let mut record_ttl = None;
+        let record_type = loop {
+            let token = tokens.next().ok_or(ParseError::MissingType { line })?;
+            let upper = token.text.to_uppercase();
+            if token.text.starts_with(|c: char| c.is_ascii_digit()) {
+                record_ttl = Some(ttl(&token.text).ok_or_else(|| ParseError::Ttl {
+                    line,
+                    ttl: token.text.clone(),
+                })?);
+            } else if upper == "IN" {
+                continue;
+            } else if matches!(upper.as_str(), "CH" | "HS" | "CS") {
+                return Err(ParseError::Class { line, class: upper });
+            } else {
+                break upper;
+            }
+        };
+        let data = tokens.collect::<Vec<_>>();
+
+        let ttl = record_ttl.or(self.default_ttl).or(self.last_ttl);
+        self.last_ttl = ttl;
+
+        let name = match self.relative(&owner) {
+            Ok(name) => name,
+            Err(()) => {
+                self.warn(Warning::OutOfZone { line, name: owner });
+                return Ok(());
+            }
+        };
+
+        match record_type.as_str() {
+            "SOA" => {
+                self.warn(Warning::Soa { line });
+                return Ok(());
+            }
+            "NS" if name.is_none() => {
+                self.warn(Warning::ApexNs { line });
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let content = match self.content(line, &record_type, &data)? {
+            Some(content) => content,
+            None => {
+                self.warn(Warning::Unsupported { line, record_type });
+                return Ok(());
+            }
+        };
+
+        let mut endpoint = CreateDns::builder();
+        endpoint.domain(self.domain.clone()).record(content);
+        if let Some(name) = name {
+            endpoint.name(name);
+        }
+        if let Some(ttl) = ttl {
+            endpoint.ttl(ttl);
+        }
+        let endpoint = endpoint.build().map_err(|err| ParseError::Name {
+            line,
+            message: err.to_string(),
+        })?;
+        self.records.push(endpoint);
+        Ok(())
+    }
+
+    /// The content of a record, or `None` if its type is not supported.
+    fn content(
+        &self,
+        line: usize,
+        record_type: &str,
+        data: &[&Token],
+    ) -> ParseResult<Option<DnsContent>> {
+        let invalid = |message: &str| ParseError::Data {
+            line,
+            record_type: record_type.into(),
+            message: message.into(),
+        };
+        let count = |expected: usize| {
+            if data.len() == expected {
+                Ok(())
+            } else {
+                Err(invalid(&format!(
+                    "expected {} fields, found {}",
+                    expected,
+                    data.len()
+                )))
+            }
+        };
+        let priority = |token: &Token| {
+            token
+                .text
+                .parse::<u16>()
+                .map_err(|_| invalid(&format!("invalid priority `{}`", token.text)))
+        };
+
+        Ok(Some(match record_type {
+            "A" => {
+                count(1)?;
+                DnsContent::A {
+                    content: data[0]
+                        .text
+                        .parse::<Ipv4Addr>()
+                        .map_err(|_| invalid(&format!("invalid address `{}`", data[0].text)))?,
+                }
+            }
+            "AAAA" => {
+                count(1)?;
+                DnsContent::Aaaa {
+                    content: data[0]
+                        .text
+                        .parse::<Ipv6Addr>()
+                        .map_err(|_| invalid(&format!("invalid address `{}`", data[0].text)))?,
+                }
+            }
+            "CNAME" => {
+                count(1)?;
+                DnsContent::Cname {
+                    content: self.absolute(&data[0].text),
+                }
+            }
+            "NS" => {
+                count(1)?;
+                DnsContent::Ns {
+                    content: self.absolute(&data[0].text),
+                }
+            }
+            "MX" => {
+                count(2)?;
+                DnsContent::Mx {
+                    priority: priority(data[0])?,
+                    content: self.absolute(&data[1].text),
+                }
+            }
+            "SRV" => {
+                count(4)?;
+                DnsContent::Srv {
+                    priority: priority(data[0])?,
+                    content: format!(
+                        "{} {} {}",
+                        data[1].text,
+                        data[2].text,
+                        self.absolute(&data[3].text),
+                    ),
+                }
+            }
+            "TXT" => {
+                if data.is_empty() {
+                    return Err(invalid("missing text"));
+                }
+                // Strings longer than 255 bytes are split, and joined back by resolvers.
+                DnsContent::Txt {
+                    content: data.iter().map(|token| token.text.as_str()).collect(),
+                }
+            }
+            "CAA" => {
+                count(3)?;
+                DnsContent::Caa {
+                    content: format!(
+                        "{} {} \"{}\"",
+                        data[0].text,
+                        data[1].text,
+                        data[2].text.replace('\\', "\\\\").replace('"', "\\\""),
+                    ),
+                }
+            }
+            "TLSA" => {
+                if data.len() < 4 {
+                    return Err(invalid("expected at least 4 fields"));
+                }
+                DnsContent::Tlsa {
+                    content: format!(
+                        "{} {} {} {}",
+                        data[0].text,
+                        data[1].text,
+                        data[2].text,
+                        // Long digests may be split over several fields.
+                        data[3..]
+                            .iter()
+                            .map(|token| token.text.as_str())
+                            .collect::<String>(),
+                    ),
+                }
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    fn warn(&mut self, warning: Warning) {
+        warn!(target: "porkbun", "{}", warning);
+        self.warnings.push(warning);
+    }
+}
+
+/// Parse a master file into the records to create in the Porkbun domain `domain`.
+///
+/// Names are relative to `domain` until a `$ORIGIN` directive says otherwise. The SOA record
+/// and the NS records of the domain itself are skipped since Porkbun manages them, as are
+/// records of types Porkbun does not support and records outside of the domain; all of them
+/// are reported as warnings. `$INCLUDE` and `$GENERATE` are not supported.
+pub fn parse(domain: &str, input: &str) -> ParseResult<Import> {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    let mut parser = Parser {
+        origin: domain.clone(),
+        domain,
+        default_ttl: None,
+        last_ttl: None,
+        last_owner: None,
+        records: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    for entry in entries(input)? {
+        let first = &entry.tokens[0];
+        if !entry.indented && !first.quoted && first.text.starts_with('$') {
+            parser.directive(&entry)?;
+        } else {
+            parser.record(&entry)?;
+        }
+    }
+
+    Ok(Import {
+        records: parser.records,
+        warnings: parser.warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::{api::Endpoint, zone::render, Record};
+
+    use super::{parse, ParseError, Warning};
+
+    fn bodies(input: &str) -> Vec<Value> {
+        parse("example.com", input)
+            .unwrap()
+            .records
+            .iter()
+            .map(|record| {
+                assert_eq!(record.endpoint(), "dns/create/example.com");
+                Value::Object(record.body())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn records() {
+        let zone = r#"
+$ORIGIN example.com.
+$TTL 1h
+@       IN  SOA ns1.example.net. hostmaster.example.com. (
+                2024010101 ; serial
+                7200 3600 1209600 3600 )
+        IN  NS  ns1.example.net.
+        IN  A   192.0.2.1
+        300 IN  MX  10 mail
+www         CNAME @
+_sip._tcp   SRV 10 5 5060 sip.example.net.
+txt     TXT ( "v=spf1 "
+              "mx -all" ) ; joined
+caa     CAA 0 issue "letsencrypt.org"
+$ORIGIN sub.example.com.
+host    AAAA 2001:db8::1
+"#;
+        assert_eq!(
+            bodies(zone),
+            [
+                json!({"type": "A", "content": "192.0.2.1", "ttl": "3600"}),
+                json!({"type": "MX", "prio": "10", "content": "mail.example.com", "ttl": "300"}),
+                json!({"name": "www", "type": "CNAME", "content": "example.com", "ttl": "3600"}),
+                json!({
+                    "name": "_sip._tcp",
+                    "type": "SRV",
+                    "prio": "10",
+                    "content": "5 5060 sip.example.net",
+                    "ttl": "3600",
+                }),
+                json!({"name": "txt", "type": "TXT", "content": "v=spf1 mx -all", "ttl": "3600"}),
+                json!({
+                    "name": "caa",
+                    "type": "CAA",
+                    "content": "0 issue \"letsencrypt.org\"",
+                    "ttl": "3600",
+                }),
+                json!({"name": "host.sub", "type": "AAAA", "content": "2001:db8::1", "ttl": "3600"}),
+            ],
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let zone = "\
+@ 3600 IN SOA ns1 hostmaster 1 2 3 4 5
+@ NS ns1.example.net.
+sub NS ns1.example.net.
+@ HINFO \"PC\" \"Linux\"
+www.example.org. A 192.0.2.1
+";
+        let import = parse("example.com", zone).unwrap();
+        assert_eq!(import.records.len(), 1);
+        assert_eq!(
+            import.warnings,
+            [
+                Warning::Soa { line: 1 },
+                Warning::ApexNs { line: 2 },
+                Warning::Unsupported {
+                    line: 4,
+                    record_type: "HINFO".into(),
+                },
+                Warning::OutOfZone {
+                    line: 5,
+                    name: "www.example.org".into(),
+                },
+            ],
+        );
+        assert_eq!(
+            import.warnings[2].to_string(),
+            "line 4: unsupported record type `HINFO`",
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let zone = r#"@ TXT "say \"hi\"; caf\195\169" unquoted\ text"#;
+        assert_eq!(
+            bodies(zone),
+            [json!({"type": "TXT", "content": "say \"hi\"; caféunquoted text"})],
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = |zone| parse("example.com", zone).unwrap_err();
+        assert_eq!(
+            err("@ A 192.0.2.1\n@ TXT \"open\n"),
+            ParseError::UnterminatedString { line: 2 },
+        );
+        assert_eq!(
+            err("@ SOA ( 1 2\n3 4 5\n"),
+            ParseError::Parentheses { line: 1 }
+        );
+        assert_eq!(
+            err("\n$INCLUDE other.zone\n"),
+            ParseError::Directive {
+                line: 2,
+                directive: "$INCLUDE".into(),
+            },
+        );
+        assert_eq!(err(" A 192.0.2.1\n"), ParseError::MissingOwner { line: 1 });
+        assert_eq!(
+            err("@ 1x A 192.0.2.1\n").to_string(),
+            "line 1: invalid TTL `1x`",
+        );
+        assert_eq!(
+            err("\n\nwww A 2001:db8::1\n").to_string(),
+            "line 3: invalid A record: invalid address `2001:db8::1`",
+        );
+        assert_eq!(
+            err("@ A 192.0.2.1\nw/w A 192.0.2.2\n").to_string(),
+            r#"line 2: invalid name: "w/w" has an invalid label "w/w""#,
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let record = |name: &str, record_type: &str, content: &str, prio: &str| Record {
+            id: "1".into(),
+            name: name.into(),
+            record_type: record_type.into(),
+            content: content.into(),
+            ttl: "600".into(),
+            priority: Some(prio.into()),
+            notes: None,
+        };
+        let records = vec![
+            record("example.com", "MX", "mail.example.com", "10"),
+            record("www.example.com", "TXT", &"x\"y".repeat(100), "0"),
+            record(
+                "_sip._tcp.example.com",
+                "SRV",
+                "5 5060 sip.example.com",
+                "20",
+            ),
+        ];
+
+        let bodies = bodies(&render("example.com", &records));
+        assert_eq!(bodies[0]["content"], "mail.example.com");
+        assert_eq!(bodies[0]["prio"], "10");
+        assert_eq!(bodies[1]["content"], "5 5060 sip.example.com");
+        assert_eq!(bodies[1]["prio"], "20");
+        assert_eq!(bodies[2]["content"], "x\"y".repeat(100));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt::Write};
+
+use crate::Record;
+
+/// The longest string a TXT record may hold.
+const MAX_STRING: usize = 255;
+
+/// Record types whose content ends with a domain name.
+const NAME_TYPES: &[&str] = &["ALIAS", "CNAME", "MX", "NS", "SRV"];
+
+/// Render the records of the domain `origin`, such as those of a
+/// [`RetrieveResponse`](crate::RetrieveResponse), as a master file.
+///
+/// Names are written relative to `origin` and `$TTL` is the most common TTL, so only the
+/// other ones are written out. Records are sorted, so the same records in any order always
+/// render the same. `ALIAS` records, which are not standard, are written as comments.
+pub fn render(origin: &str, records: &[Record]) -> String {
+    let origin = origin.trim_end_matches('.').to_lowercase();
+    let default_ttl = default_ttl(records);
+
+    let mut lines = records
+        .iter()
+        .map(|record| Line::new(&origin, record))
+        .collect::<Vec<_>>();
+    lines.sort_by(Line::cmp);
+
+    let mut out = format!("$ORIGIN {}.\n", origin);
+    if let Some(ttl) = default_ttl {
+        writeln!(out, "$TTL {}", ttl).unwrap();
+    }
+    out.push('\n');
+
+    for line in lines {
+        if line.record_type == "ALIAS" {
+            out.push_str("; ");
+        }
+        out.push_str(&line.name);
+        if default_ttl != Some(line.ttl) {
+            write!(out, " {}", line.ttl).unwrap();
+        }
+        writeln!(out, " IN {} {}", line.record_type, line.data).unwrap();
+    }
+
+    out
+}
+
+/// The most common TTL, the lowest one on ties.
+fn default_ttl(records: &[Record]) -> Option<u32> {
+    let mut counts = BTreeMap::new();
+    for record in records {
+        *counts.entry(ttl(record)).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|(ttl_a, count_a), (ttl_b, count_b)| {
+            count_a.cmp(count_b).then_with(|| ttl_b.cmp(ttl_a))
+        })
+        .map(|(ttl, _)| ttl)
+}
+
+fn ttl(record: &Record) -> u32 {
+    record.ttl.parse().unwrap_or_default()
+}
+
+struct Line {
+    /// The name relative to the origin, `@` for the origin itself.
+    name: String,
+    ttl: u32,
+    record_type: String,
+    priority: u16,
+    data: String,
+}
+
+impl Line {
+    fn new(origin: &str, record: &Record) -> Self {
+        let record_type = record.record_type.to_uppercase();
+        let priority = match record_type.as_str() {
+            "MX" | "SRV" => record
+                .priority
+                .as_deref()
+                .and_then(|prio| prio.parse().ok())
+                .unwrap_or_default(),
+            _ => 0,
+        };
+
+        let mut data = match record_type.as_str() {
+            "TXT" => txt(&record.content),
+            _ if NAME_TYPES.contains(&record_type.as_str()) => absolute(&record.content),
+            _ => record.content.clone(),
+        };
+        if matches!(record_type.as_str(), "MX" | "SRV") {
+            data = format!("{} {}", priority, data);
+        }
+
+        Self {
+            name: relative(origin, &record.name),
+            ttl: ttl(record),
+            record_type,
+            priority,
+            data,
+        }
+    }
+
+    /// The origin first, then names ordered from the top of the tree down, so that related
+    /// names stay together.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let labels = |name: &str| {
+            if name == "@" {
+                Vec::new()
+            } else {
+                name.rsplit('.').map(str::to_string).collect::<Vec<_>>()
+            }
+        };
+        labels(&self.name)
+            .cmp(&labels(&other.name))
+            .then_with(|| self.record_type.cmp(&other.record_type))
+            .then_with(|| self.priority.cmp(&other.priority))
+            .then_with(|| self.data.cmp(&other.data))
+            .then_with(|| self.ttl.cmp(&other.ttl))
+    }
+}
+
+fn relative(origin: &str, name: &str) -> String {
+    let name = name.trim_end_matches('.').to_lowercase();
+    if name == origin {
+        "@".into()
+    } else {
+        match name
+            .strip_suffix(origin)
+            .and_then(|name| name.strip_suffix('.'))
+        {
+            Some(name) => name.into(),
+            None => format!("{}.", name),
+        }
+    }
+}
+
+/// Make the domain name ending the content absolute.
+fn absolute(content: &str) -> String {
+    let content = content.trim();
+    if content.ends_with('.') {
+        content.into()
+    } else {
+        format!("{}.", content)
+    }
+}
+
+/// Quote TXT content, split into strings of at most 255 bytes.
+///
+/// Content which is already quoted is kept as it is.
+fn txt(content: &str) -> String {
+    if content.starts_with('"') {
+        return content.into();
+    }
+
+    if content.is_empty() {
+        return "\"\"".into();
+    }
+
+    content
+        .as_bytes()
+        .chunks(MAX_STRING)
+        .map(|chunk| {
+            let mut string = String::with_capacity(chunk.len() + 2);
+            string.push('"');
+            for &byte in chunk {
+                match byte {
+                    b'"' | b'\\' => {
+                        string.push('\\');
+                        string.push(byte as char);
+                    }
+                    0x20..=0x7e => string.push(byte as char),
+                    _ => write!(string, "\\{:03}", byte).unwrap(),
+                }
+            }
+            string.push('"');
+            string
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+    use crate::Record;
+
+    fn record(name: &str, record_type: &str, content: &str, ttl: u32, prio: u16) -> Record {
+        Record {
+            id: "1".into(),
+            name: name.into(),
+            record_type: record_type.into(),
+            content: content.into(),
+            ttl: ttl.to_string(),
+            priority: Some(prio.to_string()),
+            notes: None,
+        }
+    }
+
+    fn records() -> Vec<Record> {
+        vec![
+            record("www.example.com", "CNAME", "example.com", 600, 0),
+            record("example.com", "A", "192.0.2.1", 600, 0),
+            record("example.com", "MX", "mail2.example.com", 600, 20),
+            record("example.com", "MX", "mail.example.com", 600, 10),
+            record("example.com", "TXT", "v=spf1 mx -all", 3600, 0),
+            record("a.b.example.com", "AAAA", "2001:db8::1", 600, 0),
+            record("b.example.com", "A", "192.0.2.2", 600, 0),
+            record(
+                "_sip._tcp.example.com",
+                "SRV",
+                "5 5060 sip.example.com",
+                600,
+                10,
+            ),
+            record("example.com", "ALIAS", "example.net", 600, 0),
+            record("example.com", "CAA", "0 issue \"letsencrypt.org\"", 600, 0),
+        ]
+    }
+
+    #[test]
+    fn zone() {
+        assert_eq!(
+            render("example.com", &records()),
+            "$ORIGIN example.com.\n\
+             $TTL 600\n\
+             \n\
+             @ IN A 192.0.2.1\n\
+             ; @ IN ALIAS example.net.\n\
+             @ IN CAA 0 issue \"letsencrypt.org\"\n\
+             @ IN MX 10 mail.example.com.\n\
+             @ IN MX 20 mail2.example.com.\n\
+             @ 3600 IN TXT \"v=spf1 mx -all\"\n\
+             _sip._tcp IN SRV 10 5 5060 sip.example.com.\n\
+             b IN A 192.0.2.2\n\
+             a.b IN AAAA 2001:db8::1\n\
+             www IN CNAME example.com.\n",
+        );
+    }
+
+    #[test]
+    fn stable_order() {
+        let mut shuffled = records();
+        shuffled.reverse();
+        shuffled.swap(1, 4);
+        assert_eq!(
+            render("example.com.", &shuffled),
+            render("example.com", &records()),
+        );
+    }
+
+    #[test]
+    fn txt_quoting() {
+        let long = "a".repeat(300);
+        let records = vec![
+            record("example.com", "TXT", &long, 600, 0),
+            record("q.example.com", "TXT", "say \"hi\" \\ bye", 600, 0),
+            record("u.example.com", "TXT", "caf\u{e9}", 600, 0),
+            record("other.example.org", "TXT", "", 600, 0),
+        ];
+        let zone = render("example.com", &records);
+        let lines = zone.lines().skip(3).collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            format!("@ IN TXT \"{}\" \"{}\"", "a".repeat(255), "a".repeat(45)),
+        );
+        assert_eq!(lines[1], "other.example.org. IN TXT \"\"");
+        assert_eq!(lines[2], "q IN TXT \"say \\\"hi\\\" \\\\ bye\"");
+        assert_eq!(lines[3], "u IN TXT \"caf\\195\\169\"");
+    }
+}
@@ -0,0 +1,101 @@
+use porkbun_rs::test::{FakePorkbun, FakeServer};
+use std::process::{Command, Output};
+
+const APIKEY: &str = "pk1_test";
+const SECRETAPIKEY: &str = "sk1_test";
+
+fn serve() -> (FakePorkbun, FakeServer) {
+    let fake = FakePorkbun::new()
+        .with_domain("example.com")
+        .with_credentials(APIKEY, SECRETAPIKEY)
+        .with_your_ip("192.0.2.7");
+    let server = fake.serve().unwrap();
+    (fake, server)
+}
+
+fn porkbun(server: &FakeServer, apikey: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_porkbun"))
+        .args(args)
+        .arg("--config")
+        .arg(std::env::temp_dir().join("porkbun-rs-cli-missing.toml"))
+        .env("PORKBUN_API_URL", server.url().as_str())
+        .env("PORKBUN_API_KEY", apikey)
+        .env("PORKBUN_SECRET_API_KEY", SECRETAPIKEY)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn ping() {
+    let (_, server) = serve();
+    let output = porkbun(&server, APIKEY, &["ping"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(stdout(&output), "192.0.2.7\n");
+}
+
+#[test]
+fn create_and_list() {
+    let (fake, server) = serve();
+    let args = [
+        "dns",
+        "create",
+        "example.com",
+        "--type",
+        "A",
+        "--content",
+        "192.0.2.1",
+        "--name",
+        "www",
+    ];
+    let output = porkbun(&server, APIKEY, &args);
+    assert!(output.status.success(), "{:?}", output);
+    let id = stdout(&output).trim().to_string();
+
+    let records = fake.records("example.com").unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].id, id);
+    assert_eq!(records[0].name, "www.example.com");
+
+    let output = porkbun(
+        &server,
+        APIKEY,
+        &["--output", "json", "dns", "list", "example.com"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(listed[0]["id"], id.as_str());
+    assert_eq!(listed[0]["content"], "192.0.2.1");
+}
+
+#[test]
+fn exit_codes() {
+    let (_, server) = serve();
+
+    let output = porkbun(&server, "pk1_wrong", &["ping"]);
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+
+    let output = porkbun(&server, APIKEY, &["dns", "list", "example.org"]);
+    assert_eq!(output.status.code(), Some(4), "{:?}", output);
+
+    let args = [
+        "dns",
+        "create",
+        "example.com",
+        "--type",
+        "TXT",
+        "--content",
+        "hello",
+    ];
+    let output = porkbun(&server, APIKEY, &args);
+    assert!(output.status.success(), "{:?}", output);
+    let output = porkbun(&server, APIKEY, &args);
+    assert_eq!(output.status.code(), Some(5), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Duplicate record"));
+
+    let output = porkbun(&server, APIKEY, &["dns", "delete", "example.com"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}